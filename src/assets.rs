@@ -0,0 +1,304 @@
+use std::path::PathBuf;
+
+use crate::{
+    platform::Platform,
+    version::GodotVersion,
+};
+
+/// Placeholder in naming templates for the full version, e.g. `4.2.1-stable`.
+const VERSION_PLACEHOLDER: &str = "{v}";
+
+/// A version number without its release stage, used to compare against naming rule ranges.
+pub type Number = (u32, u32, u32);
+
+/// How release assets were named for a platform over a range of Godot versions.
+struct NamingRule {
+    platform: Platform,
+    mono: bool,
+    /// First version using this naming, inclusive.
+    since: Number,
+    /// First version no longer using this naming, if any.
+    until: Option<Number>,
+    /// Name of the zip published with the release.
+    zip: &'static str,
+    /// Path of the engine inside the zip. For macOS, this is the app bundle.
+    binary: &'static str,
+}
+
+const fn rule(
+    platform: Platform,
+    mono: bool,
+    since: Number,
+    until: Option<Number>,
+    zip: &'static str,
+    binary: &'static str,
+) -> NamingRule {
+    NamingRule { platform, mono, since, until, zip, binary }
+}
+
+const V2_0: Number = (2, 0, 0);
+const V3_0: Number = (3, 0, 0);
+const V3_1: Number = (3, 1, 0);
+const V3_2_4: Number = (3, 2, 4);
+const V4_0: Number = (4, 0, 0);
+const V4_2: Number = (4, 2, 0);
+const V4_3: Number = (4, 3, 0);
+
+/// Every naming convention Godot has used for its release assets. Godot 2.x and 3.x call Linux
+/// builds `x11` and macOS builds `osx`, while 4.x calls them `linux` and `macos`. Mono builds
+/// are zipped in a directory alongside GodotSharp, except on macOS where it's in the app bundle.
+static NAMING_RULES: &[NamingRule] = &[
+    // Windows.
+    rule(Platform::Windows64, false, V2_0, None, "Godot_v{v}_win64.exe.zip", "Godot_v{v}_win64.exe"),
+    rule(Platform::Windows32, false, V2_0, None, "Godot_v{v}_win32.exe.zip", "Godot_v{v}_win32.exe"),
+    rule(Platform::Windows64, true, V3_0, None, "Godot_v{v}_mono_win64.zip", "Godot_v{v}_mono_win64/Godot_v{v}_mono_win64.exe"),
+    rule(Platform::Windows32, true, V3_0, None, "Godot_v{v}_mono_win32.zip", "Godot_v{v}_mono_win32/Godot_v{v}_mono_win32.exe"),
+    rule(Platform::WindowsArm64, false, V4_3, None, "Godot_v{v}_windows_arm64.exe.zip", "Godot_v{v}_windows_arm64.exe"),
+    rule(Platform::WindowsArm64, true, V4_3, None, "Godot_v{v}_mono_windows_arm64.zip", "Godot_v{v}_mono_windows_arm64/Godot_v{v}_mono_windows_arm64.exe"),
+
+    // macOS.
+    rule(Platform::MacOS, false, V2_0, Some(V3_0), "Godot_v{v}_osx.fat.zip", "Godot.app"),
+    rule(Platform::MacOS, false, V3_0, Some(V3_2_4), "Godot_v{v}_osx.64.zip", "Godot.app"),
+    rule(Platform::MacOS, false, V3_2_4, Some(V4_0), "Godot_v{v}_osx.universal.zip", "Godot.app"),
+    rule(Platform::MacOS, false, V4_0, None, "Godot_v{v}_macos.universal.zip", "Godot.app"),
+    rule(Platform::MacOS, true, V3_0, Some(V3_2_4), "Godot_v{v}_mono_osx.64.zip", "Godot_mono.app"),
+    rule(Platform::MacOS, true, V3_2_4, Some(V4_0), "Godot_v{v}_mono_osx.universal.zip", "Godot_mono.app"),
+    rule(Platform::MacOS, true, V4_0, None, "Godot_v{v}_mono_macos.universal.zip", "Godot_mono.app"),
+
+    // Linux.
+    rule(Platform::Linux64, false, V2_0, Some(V4_0), "Godot_v{v}_x11.64.zip", "Godot_v{v}_x11.64"),
+    rule(Platform::Linux32, false, V2_0, Some(V4_0), "Godot_v{v}_x11.32.zip", "Godot_v{v}_x11.32"),
+    rule(Platform::Linux64, false, V4_0, None, "Godot_v{v}_linux.x86_64.zip", "Godot_v{v}_linux.x86_64"),
+    rule(Platform::Linux32, false, V4_0, None, "Godot_v{v}_linux.x86_32.zip", "Godot_v{v}_linux.x86_32"),
+    rule(Platform::Linux64, true, V3_0, Some(V4_0), "Godot_v{v}_mono_x11_64.zip", "Godot_v{v}_mono_x11_64/Godot_v{v}_mono_x11.64"),
+    rule(Platform::Linux32, true, V3_0, Some(V4_0), "Godot_v{v}_mono_x11_32.zip", "Godot_v{v}_mono_x11_32/Godot_v{v}_mono_x11.32"),
+    rule(Platform::Linux64, true, V4_0, None, "Godot_v{v}_mono_linux_x86_64.zip", "Godot_v{v}_mono_linux_x86_64/Godot_v{v}_mono_linux.x86_64"),
+    rule(Platform::Linux32, true, V4_0, None, "Godot_v{v}_mono_linux_x86_32.zip", "Godot_v{v}_mono_linux_x86_32/Godot_v{v}_mono_linux.x86_32"),
+    rule(Platform::LinuxArm64, false, V4_2, None, "Godot_v{v}_linux.arm64.zip", "Godot_v{v}_linux.arm64"),
+    rule(Platform::LinuxArm32, false, V4_2, None, "Godot_v{v}_linux.arm32.zip", "Godot_v{v}_linux.arm32"),
+    rule(Platform::LinuxArm64, true, V4_2, None, "Godot_v{v}_mono_linux_arm64.zip", "Godot_v{v}_mono_linux_arm64/Godot_v{v}_mono_linux.arm64"),
+    rule(Platform::LinuxArm32, true, V4_2, None, "Godot_v{v}_mono_linux_arm32.zip", "Godot_v{v}_mono_linux_arm32/Godot_v{v}_mono_linux.arm32"),
+
+    // Linux headless and server builds. Godot 4 dropped these for the --headless flag.
+    rule(Platform::LinuxServer, false, V2_0, Some(V4_0), "Godot_v{v}_linux_server.64.zip", "Godot_v{v}_linux_server.64"),
+    rule(Platform::LinuxHeadless, false, V3_1, Some(V4_0), "Godot_v{v}_linux_headless.64.zip", "Godot_v{v}_linux_headless.64"),
+    rule(Platform::LinuxServer, true, V3_1, Some(V4_0), "Godot_v{v}_mono_linux_server_64.zip", "Godot_v{v}_mono_linux_server_64/Godot_v{v}_mono_linux_server.64"),
+    rule(Platform::LinuxHeadless, true, V3_1, Some(V4_0), "Godot_v{v}_mono_linux_headless_64.zip", "Godot_v{v}_mono_linux_headless_64/Godot_v{v}_mono_linux_headless.64"),
+];
+
+/// Names of a release's zip and the engine inside it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetNames {
+    pub zip: String,
+    /// Path of the engine relative to where the zip is extracted.
+    pub binary: PathBuf,
+}
+
+impl NamingRule {
+    fn applies_to(&self, version: &GodotVersion, platform: Platform, mono: bool) -> bool {
+        let number = (version.major, version.minor, version.patch);
+        self.platform == platform &&
+            self.mono == mono &&
+            self.since <= number &&
+            self.until.map_or(true, |until| number < until)
+    }
+}
+
+/// Resolve the asset names for a version on a platform. Returns `None` if Godot didn't publish a
+/// build of that version for the platform.
+pub fn resolve(version: &GodotVersion, platform: Platform, mono: bool) -> Option<AssetNames> {
+    let rule = NAMING_RULES.iter()
+        .find(|rule| rule.applies_to(version, platform, mono))?;
+    let full_version = version.full_version();
+    Some(AssetNames {
+        zip: rule.zip.replace(VERSION_PLACEHOLDER, &full_version),
+        binary: PathBuf::from(rule.binary.replace(VERSION_PLACEHOLDER, &full_version)),
+    })
+}
+
+/// The first version with a build for a platform, if Godot has ever published one.
+pub fn first_version(platform: Platform, mono: bool) -> Option<Number> {
+    NAMING_RULES.iter()
+        .filter(|rule| rule.platform == platform && rule.mono == mono)
+        .map(|rule| rule.since)
+        .min()
+}
+
+/// Find which platform a version's zip was built for, if it's one fyg knows about.
+pub fn identify(version: &GodotVersion, mono: bool, zip_name: &str) -> Option<Platform> {
+    Platform::ALL.iter()
+        .copied()
+        .find(|&platform| resolve(version, platform, mono)
+            .is_some_and(|names| names.zip == zip_name))
+}
+
+/// Name of the export templates package published with a release. Returns `None` for versions
+/// without one, since Mono templates are only published from Godot 3.
+pub fn templates(version: &GodotVersion, mono: bool) -> Option<String> {
+    let number = (version.major, version.minor, version.patch);
+    if number < V2_0 || (mono && number < V3_0) {
+        return None;
+    }
+    let variant = if mono { "_mono" } else { "" };
+    Some(format!("Godot_v{}{}_export_templates.tpz", version.full_version(), variant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Resolve the zip and binary names for a release tag, like `4.2.1-stable`.
+    fn names(tag: &str, platform: Platform, mono: bool) -> Option<(String, String)> {
+        let version = tag.parse().unwrap();
+        resolve(&version, platform, mono)
+            .map(|names| (names.zip, names.binary.to_string_lossy().into_owned()))
+    }
+
+    fn assert_names(tag: &str, platform: Platform, mono: bool, zip: &str, binary: &str) {
+        assert_eq!(names(tag, platform, mono), Some((zip.to_string(), binary.to_string())), "{} on {:?}", tag, platform);
+    }
+
+    #[test]
+    fn resolves_godot_2() {
+        assert_names("2.1.6-stable", Platform::Linux64, false, "Godot_v2.1.6-stable_x11.64.zip", "Godot_v2.1.6-stable_x11.64");
+        assert_names("2.1.6-stable", Platform::Linux32, false, "Godot_v2.1.6-stable_x11.32.zip", "Godot_v2.1.6-stable_x11.32");
+        assert_names("2.1.6-stable", Platform::MacOS, false, "Godot_v2.1.6-stable_osx.fat.zip", "Godot.app");
+        assert_names("2.1.6-stable", Platform::LinuxServer, false, "Godot_v2.1.6-stable_linux_server.64.zip", "Godot_v2.1.6-stable_linux_server.64");
+        // Mono builds and headless builds came later.
+        assert_eq!(names("2.1.6-stable", Platform::Linux64, true), None);
+        assert_eq!(names("2.1.6-stable", Platform::LinuxHeadless, false), None);
+    }
+
+    #[test]
+    fn resolves_godot_3_0() {
+        assert_names("3.0-stable", Platform::Linux64, false, "Godot_v3.0-stable_x11.64.zip", "Godot_v3.0-stable_x11.64");
+        assert_names("3.0-stable", Platform::MacOS, false, "Godot_v3.0-stable_osx.64.zip", "Godot.app");
+        assert_names("3.0-stable", Platform::Windows64, false, "Godot_v3.0-stable_win64.exe.zip", "Godot_v3.0-stable_win64.exe");
+        assert_names(
+            "3.0-stable",
+            Platform::Linux64,
+            true,
+            "Godot_v3.0-stable_mono_x11_64.zip",
+            "Godot_v3.0-stable_mono_x11_64/Godot_v3.0-stable_mono_x11.64",
+        );
+    }
+
+    #[test]
+    fn resolves_godot_3_2_3() {
+        assert_names("3.2.3-stable", Platform::Linux64, false, "Godot_v3.2.3-stable_x11.64.zip", "Godot_v3.2.3-stable_x11.64");
+        assert_names("3.2.3-stable", Platform::MacOS, false, "Godot_v3.2.3-stable_osx.64.zip", "Godot.app");
+        assert_names(
+            "3.2.3-stable",
+            Platform::LinuxServer,
+            false,
+            "Godot_v3.2.3-stable_linux_server.64.zip",
+            "Godot_v3.2.3-stable_linux_server.64",
+        );
+        assert_names(
+            "3.2.3-stable",
+            Platform::LinuxHeadless,
+            false,
+            "Godot_v3.2.3-stable_linux_headless.64.zip",
+            "Godot_v3.2.3-stable_linux_headless.64",
+        );
+        assert_names(
+            "3.2.3-stable",
+            Platform::Linux64,
+            true,
+            "Godot_v3.2.3-stable_mono_x11_64.zip",
+            "Godot_v3.2.3-stable_mono_x11_64/Godot_v3.2.3-stable_mono_x11.64",
+        );
+        assert_names("3.2.3-stable", Platform::MacOS, true, "Godot_v3.2.3-stable_mono_osx.64.zip", "Godot_mono.app");
+        assert_names(
+            "3.2.3-stable",
+            Platform::LinuxServer,
+            true,
+            "Godot_v3.2.3-stable_mono_linux_server_64.zip",
+            "Godot_v3.2.3-stable_mono_linux_server_64/Godot_v3.2.3-stable_mono_linux_server.64",
+        );
+    }
+
+    #[test]
+    fn resolves_x11_until_godot_4() {
+        assert_names("3.5-stable", Platform::Linux64, false, "Godot_v3.5-stable_x11.64.zip", "Godot_v3.5-stable_x11.64");
+        assert_names("3.5-stable", Platform::MacOS, false, "Godot_v3.5-stable_osx.universal.zip", "Godot.app");
+        assert_names("3.6-stable", Platform::Linux64, false, "Godot_v3.6-stable_x11.64.zip", "Godot_v3.6-stable_x11.64");
+        assert_names(
+            "3.6-stable",
+            Platform::Linux64,
+            true,
+            "Godot_v3.6-stable_mono_x11_64.zip",
+            "Godot_v3.6-stable_mono_x11_64/Godot_v3.6-stable_mono_x11.64",
+        );
+    }
+
+    #[test]
+    fn resolves_godot_4_0() {
+        assert_names("4.0-stable", Platform::Linux64, false, "Godot_v4.0-stable_linux.x86_64.zip", "Godot_v4.0-stable_linux.x86_64");
+        assert_names("4.0-stable", Platform::MacOS, false, "Godot_v4.0-stable_macos.universal.zip", "Godot.app");
+        assert_names("4.0-stable", Platform::Windows64, false, "Godot_v4.0-stable_win64.exe.zip", "Godot_v4.0-stable_win64.exe");
+        assert_names(
+            "4.0-stable",
+            Platform::Linux64,
+            true,
+            "Godot_v4.0-stable_mono_linux_x86_64.zip",
+            "Godot_v4.0-stable_mono_linux_x86_64/Godot_v4.0-stable_mono_linux.x86_64",
+        );
+        assert_eq!(names("4.0-stable", Platform::LinuxHeadless, false), None);
+        assert_eq!(names("4.0-stable", Platform::LinuxArm64, false), None);
+    }
+
+    #[test]
+    fn resolves_linux_arm_from_4_2() {
+        assert_names("4.2.1-stable", Platform::LinuxArm64, false, "Godot_v4.2.1-stable_linux.arm64.zip", "Godot_v4.2.1-stable_linux.arm64");
+        assert_names("4.2.1-stable", Platform::LinuxArm32, false, "Godot_v4.2.1-stable_linux.arm32.zip", "Godot_v4.2.1-stable_linux.arm32");
+        assert_eq!(names("4.1.3-stable", Platform::LinuxArm64, false), None);
+    }
+
+    #[test]
+    fn resolves_windows_arm_from_4_3() {
+        assert_names(
+            "4.3-stable",
+            Platform::WindowsArm64,
+            false,
+            "Godot_v4.3-stable_windows_arm64.exe.zip",
+            "Godot_v4.3-stable_windows_arm64.exe",
+        );
+        assert_names(
+            "4.3-stable",
+            Platform::WindowsArm64,
+            true,
+            "Godot_v4.3-stable_mono_windows_arm64.zip",
+            "Godot_v4.3-stable_mono_windows_arm64/Godot_v4.3-stable_mono_windows_arm64.exe",
+        );
+        assert_eq!(names("4.2.2-stable", Platform::WindowsArm64, false), None);
+    }
+
+    #[test]
+    fn resolves_pre_releases() {
+        assert_names("4.3-beta2", Platform::Linux64, false, "Godot_v4.3-beta2_linux.x86_64.zip", "Godot_v4.3-beta2_linux.x86_64");
+    }
+
+    #[test]
+    fn identify_round_trips() {
+        let tags = ["2.1.6-stable", "3.0-stable", "3.2.3-stable", "3.5-stable", "3.6-stable", "4.0-stable", "4.2.1-stable", "4.3-stable"];
+        for tag in tags {
+            let version = tag.parse().unwrap();
+            for mono in [false, true] {
+                for &platform in Platform::ALL {
+                    if let Some(names) = resolve(&version, platform, mono) {
+                        assert_eq!(identify(&version, mono, &names.zip), Some(platform), "{}", names.zip);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn identify_rejects_unknown_zips() {
+        let version = "4.2.1-stable".parse().unwrap();
+        assert_eq!(identify(&version, false, "Godot_v4.2.1-stable_export_templates.tpz"), None);
+        assert_eq!(identify(&version, false, "Godot_v4.2.1-stable_mono_linux_x86_64.zip"), None);
+        assert_eq!(identify(&version, true, "Godot_v4.2.1-stable_mono_linux_x86_64.zip"), Some(Platform::Linux64));
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use ring::digest;
+
+/// Name of the release asset listing the SHA-512 sums of every other asset.
+pub const SHA512_SUMS_NAME: &str = "SHA512-SUMS.txt";
+
+fn to_hex(digest: digest::Digest) -> String {
+    digest.as_ref()
+        .iter()
+        .fold(String::with_capacity(128), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+/// Compute the SHA-512 sum of the file at `path` as a lowercase hex string.
+pub fn sha512_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)
+        .with_context(|| format!("Could not open {} to compute its checksum.", path.display()))?;
+    let mut context = digest::Context::new(&digest::SHA512);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buffer[..read]);
+    }
+
+    Ok(to_hex(context.finish()))
+}
+
+/// Find the sum for `file_name` in the contents of a `SHA512-SUMS.txt` file. Each line has the
+/// format `<sum>  <file name>`, as output by `sha512sum`.
+pub fn find_sum(sums: &str, file_name: &str) -> Option<String> {
+    sums.lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim_start().trim_start_matches('*') == file_name)
+        .map(|(sum, _)| sum.to_ascii_lowercase())
+}
+
+/// Path of the file storing the checksum of a downloaded file, next to it in the cache.
+pub fn sum_path(file_path: &Path) -> PathBuf {
+    let mut sum_path = file_path.as_os_str().to_owned();
+    sum_path.push(".sha512");
+    PathBuf::from(sum_path)
+}
+
+/// Read the stored checksum of a cached file, if there is one.
+pub fn read_stored_sum(file_path: &Path) -> Result<Option<String>> {
+    let sum_path = sum_path(file_path);
+    if !sum_path.is_file() {
+        return Ok(None);
+    }
+
+    let sum = fs::read_to_string(&sum_path)
+        .with_context(|| format!("Could not read {}.", sum_path.display()))?;
+    Ok(Some(sum.trim().to_ascii_lowercase()))
+}
+
+/// Store the checksum of a cached file next to it.
+pub fn write_stored_sum(file_path: &Path, sum: &str) -> Result<()> {
+    let sum_path = sum_path(file_path);
+    fs::write(&sum_path, format!("{}\n", sum))
+        .with_context(|| format!("Could not write {}.", sum_path.display()))
+}
@@ -1,8 +1,17 @@
+use std::ffi::OsString;
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
 use clap::{Parser, Subcommand};
 
+use crate::{
+    config::ConfigKey,
+    download,
+    output::{ColorChoice, Format},
+    platform::Platform,
+    version::{GodotVersion, VersionPrefix},
+};
+
 static VERSION: LazyLock<String> = LazyLock::new(||
     format!("{} ({})", clap::crate_version!(), env!("VERGEN_GIT_SHA"))
 );
@@ -12,6 +21,28 @@ static VERSION: LazyLock<String> = LazyLock::new(||
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<CliCommand>,
+
+    /// Use engine builds for this platform instead of the one fyg is running on. Builds that can't
+    /// run here are only downloaded to the cache.
+    #[arg(long, global = true)]
+    pub platform: Option<Platform>,
+
+    /// When to color output. Overrides the `color` setting.
+    #[arg(long, global = true, value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+
+    /// Work only from the cached release index and downloads, without going online. Overrides
+    /// the `offline` setting.
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Fetch the release list again even if the cached one is still fresh.
+    #[arg(long, global = true)]
+    pub refresh: bool,
+
+    /// How to print the results of `list`, `cache show`, `project`, and `config path`.
+    #[arg(long, global = true, value_name = "FORMAT", default_value = "text")]
+    pub format: Format,
 }
 
 #[derive(Subcommand)]
@@ -21,38 +52,171 @@ pub enum CliCommand {
         /// Show all Godot engine versions available on GitHub.
         #[arg(short, long)]
         available: bool,
+
+        /// Also show pre-release versions: dev snapshots, alphas, betas, and release candidates.
+        #[arg(long, requires = "available")]
+        prerelease: bool,
+
+        /// Only show versions starting with this one, e.g. "4.2" for 4.2 and its patch releases.
+        #[arg(long, requires = "available")]
+        filter: Option<VersionPrefix>,
+
+        /// Only show versions this one or newer, e.g. "4.0".
+        #[arg(long, requires = "available")]
+        since: Option<GodotVersion>,
+
+        /// Only show the newest patch release of each minor version.
+        #[arg(long, requires = "available")]
+        latest: bool,
+
+        /// Only show Mono versions with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
     },
 
     /// Install the given Godot engine version.
     Install {
-        /// Which version to install. e.g. "3.5.1"
-        version: String,
+        /// Which version to install. e.g. "3.5.1". If none specified, install the newest version the current project
+        /// can use.
+        version: Option<GodotVersion>,
 
-        // Install the Mono version with C# support.
-        // #[arg(long)]
-        // mono: bool,
+        /// Install the Mono version with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
 
         /// Re-install if already installed.
         #[arg(short, long)]
         force: bool,
+
+        /// Download the engine again even if it's already in the cache.
+        #[arg(long)]
+        redownload: bool,
+
+        /// How many times to try downloading before giving up.
+        #[arg(long, default_value_t = download::DEFAULT_ATTEMPTS)]
+        attempts: u32,
+
+        /// Whether the engine keeps its settings and data alongside it. Overrides the
+        /// `self_contained` setting.
+        #[arg(long, value_name = "BOOL")]
+        self_contained: Option<bool>,
     },
 
     /// Uninstall the given Godot engine version.
     Uninstall {
         /// Which version to uninstall. e.g. "3.5.1"
-        version: String,
+        version: GodotVersion,
+
+        /// Uninstall the Mono version with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
     },
 
     /// Launch the given Godot engine version.
     Launch {
-        /// Which version to launch. e.g. "3.5.1"
-        version: String,
+        /// Which version to launch. e.g. "3.5.1". If none specified, use the `default_version` setting.
+        version: Option<GodotVersion>,
+
+        /// Launch the Mono version with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+
+        /// Arguments to pass through to Godot, after `--`. e.g. `fyg launch -- --verbose`
+        #[arg(last = true)]
+        args: Vec<OsString>,
     },
 
+    /// Show or set the version that `launch` and the `godot` shims use outside of a project.
+    Default {
+        /// Which version to use by default. e.g. "4.2.1". If none specified, show the current default.
+        version: Option<GodotVersion>,
+    },
+
+    /// Create `godot`, `godot4`, and `godot3` shims that run the current project's engine version.
+    Shims,
+
     /// Edit a Godot project with its associated Godot engine.
     Edit {
-        /// Path to a project directory to edit that contains a fyg.toml file. If none specified, try the current directory.
+        /// Path to a directory in the project to edit. The nearest fyg.toml file in it or its parents is used. If none
+        /// specified, start from the current directory.
         project_dir: Option<PathBuf>,
+
+        /// Edit with the Mono version with C# support, even if the project config doesn't ask for it.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+
+        /// Install the project's engine if it isn't installed, without asking. Overrides the
+        /// `auto_install` setting.
+        #[arg(long)]
+        install: bool,
+
+        /// Arguments to pass through to Godot, after `--`. e.g. `fyg edit -- --rendering-driver opengl3`
+        #[arg(last = true)]
+        args: Vec<OsString>,
+    },
+
+    /// Play a Godot project with its associated Godot engine, showing its output.
+    Run {
+        /// Path to a directory in the project to run. The nearest fyg.toml file in it or its parents is used. If none
+        /// specified, start from the current directory.
+        project_dir: Option<PathBuf>,
+
+        /// Run with the Mono version with C# support, even if the project config doesn't ask for it.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+
+        /// Install the project's engine if it isn't installed, without asking. Overrides the
+        /// `auto_install` setting.
+        #[arg(long)]
+        install: bool,
+
+        /// Arguments to pass through to Godot, after `--`. e.g. `fyg run -- res://levels/level_1.tscn`
+        #[arg(last = true)]
+        args: Vec<OsString>,
+    },
+
+    /// Export the project with one of its export presets, running its Godot engine headless.
+    Export {
+        /// Which preset from export_presets.cfg to export with. Can be left out if the project only has one.
+        preset: Option<String>,
+
+        /// Where to export to. If none specified, use the preset's export path.
+        output: Option<PathBuf>,
+
+        /// Export a debug build instead of a release build.
+        #[arg(long)]
+        debug: bool,
+
+        /// List the project's export presets instead of exporting.
+        #[arg(short, long)]
+        list: bool,
+
+        /// Export with the Mono version with C# support, even if the project config doesn't ask for it.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+    },
+
+    /// Show the project's config file, root directory, and Godot engine version.
+    Project {
+        /// Path to a directory in the project. If none specified, start from the current directory.
+        project_dir: Option<PathBuf>,
+
+        /// Show the Mono version with C# support, even if the project config doesn't ask for it.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+    },
+
+    /// Pin the project to an exact engine build by writing a fyg.lock next to its fyg.toml.
+    Lock {
+        /// Lock the Mono version with C# support, even if the project config doesn't ask for it.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+    },
+
+    /// Install, list, or remove export templates for installed Godot engine versions.
+    Templates {
+        #[command(subcommand)]
+        templates_command: TemplatesCommand,
     },
 
     /// Show or remove files from fyg's cache. Shows downloaded engine versions by default.
@@ -60,6 +224,12 @@ pub enum CliCommand {
         #[command(subcommand)]
         cache_command: Option<CacheCommand>,
     },
+
+    /// Show or change fyg's settings. Lists settings and where they're set by default.
+    Config {
+        #[command(subcommand)]
+        config_command: Option<ConfigCommand>,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -74,6 +244,78 @@ pub enum CacheCommand {
         all: bool,
 
         /// Which downloaded engine versions to remove. e.g. "3.5.1 4.0.3"
-        versions: Vec<String>,
+        versions: Vec<GodotVersion>,
+
+        /// Remove the Mono versions with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplatesCommand {
+    /// Install export templates for an installed engine version, where its editor looks for them.
+    Install {
+        /// Which version to install export templates for. e.g. "4.2.1"
+        version: GodotVersion,
+
+        /// Install export templates for the Mono version with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+
+        /// Re-install if already installed.
+        #[arg(short, long)]
+        force: bool,
+
+        /// Download the export templates again even if they're already in the cache.
+        #[arg(long)]
+        redownload: bool,
+
+        /// How many times to try downloading before giving up.
+        #[arg(long, default_value_t = download::DEFAULT_ATTEMPTS)]
+        attempts: u32,
+    },
+
+    /// List installed engine versions that have export templates.
+    List {
+        /// Only show Mono versions with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+    },
+
+    /// Remove export templates for an installed engine version.
+    Rm {
+        /// Which version to remove export templates for. e.g. "4.2.1"
+        version: GodotVersion,
+
+        /// Remove export templates for the Mono version with C# support.
+        #[arg(long, alias = "dotnet")]
+        mono: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the path to the user's config file.
+    Path,
+
+    /// List every setting, its value, and where it's set.
+    List,
+
+    /// Print a setting's value.
+    Get {
+        key: ConfigKey,
+    },
+
+    /// Change a setting in the user's config file.
+    Set {
+        key: ConfigKey,
+
+        value: String,
+    },
+
+    /// Remove a setting from the user's config file, so it falls back to its default.
+    Unset {
+        key: ConfigKey,
     },
 }
@@ -1,78 +1,347 @@
 use std::{
     env, fs,
     path::Path,
+    process::Command,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
 
 use crate::{
-    cli::CliCommand,
-    platform::{PLATFORM, Platform},
-    version::get_full_version,
+    assets::{self, AssetNames},
+    cli::{Cli, CliCommand},
+    config::{Config, Settings},
+    dirs::FygDirs,
+    engine::EngineInstall,
+    download::RetryPolicy,
+    output,
+    platform::Platform,
+    project::Project,
+    project_godot::PROJECT_GODOT_NAME,
+    release_index::{self, IndexPolicy},
+    sources,
+    version::{GodotVersion, ReleaseStage},
 };
 
 mod cache;
+mod config;
+mod default;
 mod edit;
+mod export;
 mod install;
 mod launch;
 mod list;
+mod lock;
+mod project;
+mod run;
+mod shim;
+mod templates;
 mod uninstall;
 
-pub fn get_binary_name(full_version: &str) -> String {
-    // TODO: The naming convention for binary/zip names seems to change a lot. To support all
-    // versions, might be best to use a static list that we generate.
-    let platform_suffix = if full_version.starts_with('4') {
-        match PLATFORM {
-            Platform::Windows32 => "win32.exe",
-            Platform::Windows64 => "win64.exe",
-            Platform::MacOS => "macos.universal",
-            Platform::Linux32 => "linux.x86_32",
-            Platform::Linux64 => "linux.x86_64",
-            Platform::Unsupported => "unsupported",
+/// Suffix added to a full version to name Mono install dirs, matching Godot's own asset names.
+const MONO_SUFFIX: &str = "_mono";
+
+/// Resolve the names of a version's zip and engine binary for this platform.
+pub fn get_asset_names(version: &GodotVersion, mono: bool) -> Result<AssetNames> {
+    let platform = Platform::get();
+    if let Some(asset_names) = assets::resolve(version, platform, mono) {
+        return Ok(asset_names);
+    }
+
+    let display_version = display_version(version, mono);
+    match platform.first_version(mono) {
+        Some((major, minor, patch)) => {
+            let first_version = GodotVersion { major, minor, patch, hotfix: 0, stage: ReleaseStage::Stable };
+            bail!(
+                "Version {} has no build for {}. Builds for {} are available from version {}.",
+                display_version,
+                platform,
+                platform,
+                first_version,
+            );
         }
+        None => bail!("Version {} has no build for {}.", display_version, platform),
+    }
+}
+
+/// Resolve where a version is installed for this platform.
+pub fn get_engine_install(engines_data_dir: &Path, version: &GodotVersion, mono: bool) -> Result<EngineInstall> {
+    let asset_names = get_asset_names(version, mono)?;
+    let dir = engines_data_dir.join(get_engine_dir_name(version, mono));
+    Ok(EngineInstall::new(dir, version, Platform::get(), &asset_names))
+}
+
+/// Name of the directory a version is installed and cached under. Mono and standard builds of
+/// the same version are kept side by side.
+pub fn get_engine_dir_name(version: &GodotVersion, mono: bool) -> String {
+    if mono {
+        format!("{}{}", version.full_version(), MONO_SUFFIX)
     } else {
-        match PLATFORM {
-            Platform::Windows32 => "win32.exe",
-            Platform::Windows64 => "win64.exe",
-            Platform::MacOS => "osx.universal",
-            Platform::Linux32 => "x11.32",
-            Platform::Linux64 => "x11.64",
-            Platform::Unsupported => "unsupported",
-        }
+        version.full_version()
+    }
+}
+
+/// Parse an engine dir name into its version and whether it's a Mono build. Returns `None` for
+/// directories that weren't created by fyg.
+pub fn parse_engine_dir_name(dir_name: &str) -> Option<(GodotVersion, bool)> {
+    let (full_version, mono) = match dir_name.strip_suffix(MONO_SUFFIX) {
+        Some(full_version) => (full_version, true),
+        None => (dir_name, false),
     };
-    format!("Godot_v{}_{}", &full_version, platform_suffix)
+    let version = full_version.parse().ok()?;
+    Some((version, mono))
 }
 
-#[must_use]
-fn uninstall(engines_data_dir: &Path, version: &str) -> Result<()> {
-    let full_version = get_full_version(version);
+/// Find the versions installed for this platform, newest first.
+pub fn installed_versions(engines_data_dir: &Path, mono: bool) -> Result<Vec<GodotVersion>> {
+    if !engines_data_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions = Vec::new();
+    for entry in fs::read_dir(engines_data_dir)? {
+        let entry = entry?;
+        let Some((version, is_mono)) = parse_engine_dir_name(&entry.file_name().to_string_lossy()) else {
+            continue;
+        };
+        let is_installed = get_engine_install(engines_data_dir, &version, is_mono)
+            .is_ok_and(|engine| engine.is_installed());
+        if is_mono == mono && is_installed {
+            versions.push(version);
+        }
+    }
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(versions)
+}
+
+/// Which build of a version something is for, as shown in JSON output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    Standard,
+    Mono,
+}
+
+impl Variant {
+    pub fn new(mono: bool) -> Self {
+        if mono { Self::Mono } else { Self::Standard }
+    }
+}
+
+/// Format a version for display, marking Mono builds.
+pub fn display_version(version: &GodotVersion, mono: bool) -> String {
+    if mono {
+        format!("{} (mono)", version)
+    } else {
+        version.to_string()
+    }
+}
+
+fn uninstall(engines_data_dir: &Path, version: &GodotVersion, mono: bool) -> Result<()> {
     let engine_path = engines_data_dir
-        .join(&full_version);
+        .join(get_engine_dir_name(version, mono));
     if engine_path.is_dir() {
         fs::remove_dir_all(engine_path)?;
         return Ok(());
     }
 
     Err(anyhow!("Engine install dir \"{}\" does not exist.", engine_path.to_string_lossy()))
-        .context(format!("Could not uninstall version {}.", version))
+        .context(format!("Could not uninstall version {}.", display_version(version, mono)))
+}
+
+/// Find the project containing `start_dir` and its installed engine, for commands that open the
+/// project like `edit`. If the engine isn't installed, install it when the `auto_install` setting
+/// says to or the user agrees. `action` describes the command for errors, e.g. "edit".
+pub async fn get_project_engine(
+    start_dir: &Path,
+    mono: bool,
+    config: &Config,
+    action: &str,
+) -> Result<(Project, EngineInstall)> {
+    let error_context = || format!("Can't {} project.", action);
+    let project = Project::find(start_dir, mono)
+        .with_context(error_context)?;
+
+    // Check for project.godot in this directory.
+    if !project.project_godot_path().is_file() {
+        bail!("No {} file in {}.", PROJECT_GODOT_NAME, project.godot_dir.display());
+    }
+
+    let fyg_dirs = FygDirs::get();
+
+    // Check that the project's Godot version is installed, or install it.
+    project.checked_lock()
+        .with_context(error_context)?;
+    // Locked projects and exact requirements name a version even when it isn't installed, while
+    // ranges only resolve to installed versions.
+    let resolved = project.installed_version().ok();
+    let installed = resolved.clone()
+        .filter(|version| get_engine_install(fyg_dirs.engines_data(), version, project.mono)
+            .is_ok_and(|engine| engine.is_installed()));
+    let version = match installed {
+        Some(version) => {
+            println!("Using version {}.", project.describe_version(&version));
+            version
+        }
+        None => {
+            let missing = match resolved {
+                Some(version) => format!("Godot version {} is not installed", display_version(&version, project.mono)),
+                None => format!(
+                    "No installed{} version matches {} from {}",
+                    if project.mono { " Mono" } else { "" },
+                    project.requirement,
+                    project.requirement_source,
+                ),
+            };
+            let should_install = match config.auto_install() {
+                Some(auto_install) => auto_install,
+                None => output::confirm(&format!("{}. Install it now?", missing))?,
+            };
+            if !should_install {
+                bail!("{} {}. Pass --install to install it.", error_context(), missing);
+            }
+            install::cmd_project(start_dir, mono, false, false, &RetryPolicy::default(), config)
+                .await?
+        }
+    };
+
+    let engine = get_engine_install(fyg_dirs.engines_data(), &version, project.mono)?;
+    if !engine.is_installed() {
+        bail!(
+            "{} Godot version {} is not installed.",
+            error_context(),
+            display_version(&version, project.mono),
+        );
+    }
+    project.check_lock(&engine)?;
+
+    Ok((project, engine))
 }
 
-pub async fn run_command(command: &Option<CliCommand>) -> Result<()> {
-    let Some(command) = command else {
+/// Run a command in place of fyg, so signals and exit codes go straight to and from it.
+pub fn replace_process(mut command: Command) -> Result<()> {
+    let program = command.get_program()
+        .to_owned();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        let error = command.exec();
+        Err(error).with_context(|| format!("Could not run {}.", Path::new(&program).display()))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status()
+            .with_context(|| format!("Could not run {}.", Path::new(&program).display()))?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+/// If fyg was run through one of its `godot` shims, run the engine for the current project and
+/// return `true`. Returns `false` when fyg was run as itself.
+pub fn run_shim() -> Result<bool> {
+    let mut args = env::args_os();
+    let Some(exe_name) = args.next() else {
+        return Ok(false);
+    };
+    let Some(major) = shim::from_exe_name(Path::new(&exe_name)) else {
+        return Ok(false);
+    };
+
+    shim::exec(major, args.collect())?;
+    Ok(true)
+}
+
+pub async fn run_command(cli: &Cli) -> Result<()> {
+    let Some(command) = &cli.command else {
         return Ok(());
     };
 
+    // Settings are read from the project being edited, or else the current directory.
+    let project_dir = match command {
+        CliCommand::Edit { project_dir: Some(project_dir), .. } |
+        CliCommand::Run { project_dir: Some(project_dir), .. } |
+        CliCommand::Project { project_dir: Some(project_dir), .. } => {
+            fs::canonicalize(project_dir).unwrap_or_else(|_| project_dir.clone())
+        }
+        _ => env::current_dir()?,
+    };
+    let command_line = Settings {
+        color: cli.color,
+        offline: cli.offline.then_some(true),
+        self_contained: match command {
+            CliCommand::Install { self_contained, .. } => *self_contained,
+            _ => None,
+        },
+        auto_install: match command {
+            CliCommand::Edit { install: true, .. } |
+            CliCommand::Run { install: true, .. } => Some(true),
+            _ => None,
+        },
+        ..Default::default()
+    };
+    // Listing installs, the cache, and the config itself don't need settings, so a broken config
+    // shouldn't stop them. That way it can still be fixed with `fyg config`.
+    let needs_config = !matches!(
+        command,
+        CliCommand::Config { .. } | CliCommand::Cache { .. } | CliCommand::List { available: false, .. },
+    );
+    let config = Config::load_lenient(command_line, &project_dir);
+    let config = if needs_config { config.into_checked()? } else { config };
+    output::init(config.color(), cli.format);
+    for error in config.errors() {
+        let warning = format!("Warning: {} Ignoring it.\n{}", error, error.root_cause());
+        output::warn(&warning);
+    }
+    for warning in config.warnings() {
+        output::warn(warning);
+    }
+    release_index::init(IndexPolicy {
+        offline: config.offline(),
+        ttl: release_index::ttl_from_hours(config.release_index_ttl_hours()),
+        refresh: cli.refresh,
+    });
+    sources::set_github_token(config.github_token());
+    let sources = config.sources();
+
     match &command {
-        CliCommand::List { available } => list::cmd(*available).await,
-        CliCommand::Install { version, force } => install::cmd(version, *force).await,
-        CliCommand::Uninstall { version } => uninstall::cmd(version),
-        CliCommand::Launch { version } => launch::cmd(version),
-        CliCommand::Edit { project_dir } => {
-            let default_dir = env::current_dir()?;
-            let project_dir = project_dir.as_ref()
-                .unwrap_or(&default_dir);
-            edit::cmd(project_dir)
+        CliCommand::List { available, prerelease, filter, since, latest, mono } => {
+            let list_filter = list::ListFilter {
+                prerelease: *prerelease,
+                prefix: filter.clone(),
+                since: since.clone(),
+                latest: *latest,
+            };
+            list::cmd(*available, &list_filter, *mono, &sources).await
+        },
+        CliCommand::Install { version, mono, force, redownload, attempts, .. } => {
+            let retry = RetryPolicy::with_attempts(*attempts);
+            match version {
+                Some(version) => install::cmd(version, *mono, *force, *redownload, &retry, &config, None).await,
+                None => install::cmd_project(&project_dir, *mono, *force, *redownload, &retry, &config)
+                    .await
+                    .map(|_| ()),
+            }
+        }
+        CliCommand::Uninstall { version, mono } => uninstall::cmd(version, *mono),
+        CliCommand::Launch { version, mono, args } => {
+            let Some(version) = version.clone().or_else(|| config.default_version()) else {
+                bail!("No version given. Pass one or set a default with `fyg config set default_version <version>`.");
+            };
+            launch::cmd(&version, *mono, args)
+        }
+        CliCommand::Default { version } => default::cmd(version, &config),
+        CliCommand::Shims => shim::cmd(),
+        CliCommand::Edit { mono, args, .. } => edit::cmd(&project_dir, *mono, args, &config).await,
+        CliCommand::Run { mono, args, .. } => run::cmd(&project_dir, *mono, args, &config).await,
+        CliCommand::Export { preset, output, debug, list, mono } => {
+            export::cmd(&project_dir, preset.as_deref(), output.as_deref(), *debug, *list, *mono, &config).await
         }
+        CliCommand::Project { mono, .. } => project::cmd(&project_dir, *mono),
+        CliCommand::Lock { mono } => lock::cmd(&project_dir, *mono, &sources).await,
+        CliCommand::Templates { templates_command } => templates::cmd(templates_command, &config).await,
         CliCommand::Cache { cache_command } => cache::cmd(cache_command),
+        CliCommand::Config { config_command } => config::cmd(config_command, &config),
     }
 }
@@ -1,55 +1,28 @@
-use std::fs;
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::Serialize;
 
 use crate::{
+    assets,
     cli::CacheCommand,
-    commands::get_binary_name,
+    commands::{display_version, get_engine_dir_name, parse_engine_dir_name, Variant},
     dirs::FygDirs,
+    output,
+    platform::Platform,
+    version::GodotVersion,
 };
 
 pub fn cmd(cache_command: &Option<CacheCommand>) -> Result<()> {
     let fyg_dirs = FygDirs::get();
 
     match cache_command {
-        Some(CacheCommand::Show) | None => {
-            if !fyg_dirs.engines_cache().is_dir() {
-                // Engines cache directory doesn't exist.
-                return Ok(());
-            }
-
-            let mut total_size = 0;
-
-            // List cached engine versions.
-            let read_dir = fs::read_dir(fyg_dirs.engines_cache())?;
-            for entry in read_dir {
-                let entry = entry?;
-                let version_path = entry.path();
-                if version_path.is_dir() {
-                    let file_name = entry.file_name();
-                    let full_version = file_name.to_string_lossy();
-                    let bin_name = get_binary_name(&full_version);
-                    let zip_name = format!("{}.zip", &bin_name);
-                    let zip_path = version_path
-                        .join(&zip_name);
-                    if zip_path.is_file() {
-                        let version = full_version.strip_suffix("-stable")
-                            .unwrap_or(&full_version);
-                        let metadata = zip_path.metadata()?;
-                        let byte_size = metadata.len();
-                        let formatted_size = humansize::format_size(byte_size, humansize::DECIMAL);
-                        println!("{} ({}): {}", &version, formatted_size, zip_path.display());
-
-                        total_size += byte_size;
-                    }
-                }
-            }
-
-            // Print full size of all files in cache.
-            let formatted_size = humansize::format_size(total_size, humansize::DECIMAL);
-            println!("Total: {}", formatted_size);
-        }
-        Some(CacheCommand::Rm { all, versions }) => {
+        Some(CacheCommand::Show) | None => show()?,
+        Some(CacheCommand::Rm { all, versions, mono }) => {
             if *all {
                 // TODO: Collect all dirs to be removed, print them, and confirm removal.
                 let read_dir = fs::read_dir(fyg_dirs.engines_cache())?;
@@ -63,18 +36,217 @@ pub fn cmd(cache_command: &Option<CacheCommand>) -> Result<()> {
             }
 
             for version in versions {
-                let version = version.trim();
                 let version_path = fyg_dirs.engines_cache()
-                    .join(format!("{}-stable", version));
+                    .join(get_engine_dir_name(version, *mono));
                 if version_path.is_dir() {
                     println!("Removing {}", version_path.display());
                     fs::remove_dir_all(version_path)?;
                 } else {
-                    println!("Cache for version \"{}\" not found", version);
+                    println!("Cache for version \"{}\" not found", display_version(version, *mono));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// What a cached file is.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CachedKind {
+    Engine,
+    ExportTemplates,
+}
+
+/// A file in the download cache, as shown in JSON output.
+#[derive(Serialize)]
+struct CachedFile {
+    version: GodotVersion,
+    variant: Variant,
+    kind: CachedKind,
+    /// The platform an engine is for, e.g. `linux-64`. `None` for export templates.
+    platform: Option<String>,
+    path: PathBuf,
+    /// Size in bytes.
+    size: u64,
+    /// What the file is, for text output.
+    #[serde(skip)]
+    description: String,
+}
+
+/// Everything `cache show` prints as JSON.
+#[derive(Serialize)]
+struct CacheContents {
+    files: Vec<CachedFile>,
+    /// Size of all the files in bytes.
+    total_size: u64,
+}
+
+fn show() -> Result<()> {
+    let fyg_dirs = FygDirs::get();
+
+    let mut files = Vec::new();
+    if fyg_dirs.engines_cache().is_dir() {
+        // List cached engine versions.
+        let read_dir = fs::read_dir(fyg_dirs.engines_cache())?;
+        for entry in read_dir {
+            let entry = entry?;
+            let version_path = entry.path();
+            if !version_path.is_dir() {
+                continue;
+            }
+            let file_name = entry.file_name();
+            let engine_dir_name = file_name.to_string_lossy();
+            let Some((version, mono)) = parse_engine_dir_name(&engine_dir_name) else {
+                continue;
+            };
+            // Find engine zips for any platform, since the cache may be shared.
+            for zip_entry in fs::read_dir(&version_path)? {
+                let zip_entry = zip_entry?;
+                let zip_path = zip_entry.path();
+                let zip_name = zip_entry.file_name();
+                if !zip_path.is_file() {
+                    continue;
                 }
+                let zip_name = zip_name.to_string_lossy();
+                let (kind, platform, description) = if let Some(platform) = assets::identify(&version, mono, &zip_name) {
+                    let version_str = display_version(&version, mono);
+                    let description = if platform != Platform::get() {
+                        format!("{} for {}", version_str, platform)
+                    } else {
+                        version_str
+                    };
+                    (CachedKind::Engine, Some(platform.name()), description)
+                } else if assets::templates(&version, mono).is_some_and(|tpz_name| tpz_name == zip_name) {
+                    let description = format!("{} export templates", display_version(&version, mono));
+                    (CachedKind::ExportTemplates, None, description)
+                } else {
+                    continue;
+                };
+
+                let metadata = zip_path.metadata()?;
+                files.push(CachedFile {
+                    version: version.clone(),
+                    variant: Variant::new(mono),
+                    kind,
+                    platform,
+                    path: zip_path,
+                    size: metadata.len(),
+                    description,
+                });
             }
         }
     }
 
+    let total_size = files.iter()
+        .map(|file| file.size)
+        .sum();
+    if output::is_json() {
+        return output::print_json(&CacheContents { files, total_size });
+    }
+
+    for file in &files {
+        let formatted_size = humansize::format_size(file.size, humansize::DECIMAL);
+        println!("{} ({}): {}", file.description, formatted_size, file.path.display());
+    }
+    // Print full size of all files in cache.
+    let formatted_size = humansize::format_size(total_size, humansize::DECIMAL);
+    println!("Total: {}", formatted_size);
+
     Ok(())
 }
+
+/// Remove cached versions whose files haven't been downloaded or installed from in
+/// `retention_days` days, except for the version dir named `keep`.
+pub fn prune(retention_days: u32, keep: &str) -> Result<()> {
+    let fyg_dirs = FygDirs::get();
+    if !fyg_dirs.engines_cache().is_dir() {
+        return Ok(());
+    }
+
+    let retention = Duration::from_secs(u64::from(retention_days) * 24 * 60 * 60);
+    let now = SystemTime::now();
+    let read_dir = fs::read_dir(fyg_dirs.engines_cache())?;
+    for entry in read_dir {
+        let entry = entry?;
+        let version_path = entry.path();
+        let engine_dir_name = entry.file_name();
+        let engine_dir_name = engine_dir_name.to_string_lossy();
+        if !version_path.is_dir() || engine_dir_name == keep || parse_engine_dir_name(&engine_dir_name).is_none() {
+            continue;
+        }
+
+        // A version was last used when any of its files were last modified.
+        let mut last_used = None;
+        for file_entry in fs::read_dir(&version_path)? {
+            let modified = file_entry?.metadata()?.modified()?;
+            last_used = last_used.max(Some(modified));
+        }
+        let unused_for = last_used
+            .and_then(|last_used| now.duration_since(last_used).ok())
+            .unwrap_or(Duration::MAX);
+        if unused_for > retention {
+            println!("Removing {} (unused for over {} days)", version_path.display(), retention_days);
+            fs::remove_dir_all(&version_path)
+                .with_context(|| format!("Could not remove {}.", version_path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn json_schema() {
+        let contents = CacheContents {
+            files: vec![
+                CachedFile {
+                    version: "4.2.1".parse().unwrap(),
+                    variant: Variant::Standard,
+                    kind: CachedKind::Engine,
+                    platform: Some(Platform::Windows64.name()),
+                    path: PathBuf::from("/cache/4.2.1-stable/Godot_v4.2.1-stable_win64.exe.zip"),
+                    size: 100,
+                    description: String::from("4.2.1 for Windows 64-bit"),
+                },
+                CachedFile {
+                    version: "4.2.1".parse().unwrap(),
+                    variant: Variant::Mono,
+                    kind: CachedKind::ExportTemplates,
+                    platform: None,
+                    path: PathBuf::from("/cache/4.2.1-stable_mono/Godot_v4.2.1-stable_mono_export_templates.tpz"),
+                    size: 200,
+                    description: String::from("4.2.1 (mono) export templates"),
+                },
+            ],
+            total_size: 300,
+        };
+        assert_eq!(serde_json::to_value(&contents).unwrap(), json!({
+            "files": [
+                {
+                    "version": "4.2.1-stable",
+                    "variant": "standard",
+                    "kind": "engine",
+                    "platform": "windows-64",
+                    "path": "/cache/4.2.1-stable/Godot_v4.2.1-stable_win64.exe.zip",
+                    "size": 100,
+                },
+                {
+                    "version": "4.2.1-stable",
+                    "variant": "mono",
+                    "kind": "export_templates",
+                    "platform": null,
+                    "path": "/cache/4.2.1-stable_mono/Godot_v4.2.1-stable_mono_export_templates.tpz",
+                    "size": 200,
+                },
+            ],
+            "total_size": 300,
+        }));
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::{
+    cli::ConfigCommand,
+    config::{Config, ConfigKey, UserFygConfig, HIDDEN_VALUE},
+    output,
+};
+
+/// What `config path` prints as JSON.
+#[derive(Serialize)]
+struct ConfigPath {
+    path: PathBuf,
+}
+
+pub fn cmd(config_command: &Option<ConfigCommand>, config: &Config) -> Result<()> {
+    match config_command {
+        Some(ConfigCommand::List) | None => {
+            for &key in ConfigKey::ALL {
+                match config.lookup(key) {
+                    Some((value, origin)) => println!("{} = {} ({})", key, value, origin),
+                    None => println!("{} is not set", key),
+                }
+            }
+        }
+        Some(ConfigCommand::Path) => {
+            let path = UserFygConfig::path();
+            if output::is_json() {
+                output::print_json(&ConfigPath { path })?;
+            } else {
+                println!("{}", path.display());
+            }
+        }
+        Some(ConfigCommand::Get { key }) => {
+            let Some((value, _)) = config.lookup(*key) else {
+                bail!("{} is not set.", key);
+            };
+            println!("{}", value);
+        }
+        Some(ConfigCommand::Set { key, value }) => {
+            UserFygConfig::set(*key, Some(value))?;
+            let shown_value = if key.is_secret() { HIDDEN_VALUE } else { value };
+            println!("Set {} to {} in {}", key, shown_value, UserFygConfig::path().display());
+        }
+        Some(ConfigCommand::Unset { key }) => {
+            UserFygConfig::set(*key, None)?;
+            println!("Removed {} from {}", key, UserFygConfig::path().display());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+
+use crate::{
+    commands::{get_engine_install, shim},
+    config::{Config, ConfigKey, UserFygConfig},
+    dirs::FygDirs,
+    output,
+    version::GodotVersion,
+};
+
+pub fn cmd(version: &Option<GodotVersion>, config: &Config) -> Result<()> {
+    let Some(version) = version else {
+        match config.default_version() {
+            Some(version) => println!("{}", version),
+            None => println!("No default version set."),
+        }
+        return Ok(());
+    };
+
+    UserFygConfig::set(ConfigKey::DefaultVersion, Some(&version.to_string()))?;
+    println!("Default version set to {}.", version);
+
+    let is_installed = get_engine_install(FygDirs::get().engines_data(), version, false)
+        .is_ok_and(|engine| engine.is_installed());
+    if !is_installed {
+        let warning = format!("Warning: Version {} is not installed. Install it with `fyg install {}`.", version, version);
+        output::warn(&warning);
+    }
+
+    shim::cmd()
+}
@@ -0,0 +1,173 @@
+use std::{
+    env, fs,
+    path::{self, Path},
+    process::Command,
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    commands::{display_version, get_engine_install, templates},
+    config::Config,
+    dirs::FygDirs,
+    download::RetryPolicy,
+    export_presets::{self, ExportPreset, EXPORT_PRESETS_NAME},
+    platform::Platform,
+    project::Project,
+    templates::ExportTemplates,
+    version::GodotVersion,
+};
+
+pub async fn cmd(
+    start_dir: &Path,
+    preset_name: Option<&str>,
+    output: Option<&Path>,
+    debug: bool,
+    list: bool,
+    mono: bool,
+    config: &Config,
+) -> Result<()> {
+    let project = Project::find(start_dir, mono)
+        .context("Can't export project.")?;
+    let presets_path = project.godot_dir.join(EXPORT_PRESETS_NAME);
+    if !presets_path.is_file() {
+        bail!(
+            "No {} file in {}. Add an export preset in the editor with Project > Export.",
+            EXPORT_PRESETS_NAME,
+            project.godot_dir.display(),
+        );
+    }
+    let presets = export_presets::load(&presets_path)?;
+
+    if list {
+        for preset in &presets {
+            print_preset(preset);
+        }
+        return Ok(());
+    }
+
+    let preset = match preset_name {
+        Some(preset_name) => presets.iter()
+            .find(|preset| preset.name == preset_name),
+        None if presets.len() == 1 => presets.first(),
+        None => None,
+    };
+    let Some(preset) = preset else {
+        println!("Presets in {}:", presets_path.display());
+        for preset in &presets {
+            print_preset(preset);
+        }
+        match preset_name {
+            Some(preset_name) => bail!("No preset named \"{}\".", preset_name),
+            None => bail!("Pass which preset to export."),
+        }
+    };
+
+    // Paths passed on the command line are relative to where fyg runs, while the preset's are
+    // relative to the project.
+    let output = match output {
+        Some(output) => path::absolute(output)
+            .with_context(|| format!("Could not resolve {}.", output.display()))?,
+        None if !preset.export_path.is_empty() => project.godot_dir.join(&preset.export_path),
+        None => bail!("Preset \"{}\" has no export path. Pass where to export to.", preset.name),
+    };
+
+    // Check that the project's engine and its export templates are installed.
+    let version = project.installed_version()
+        .context("Can't export project.")?;
+    println!("Using version {}.", project.describe_version(&version));
+    let fyg_dirs = FygDirs::get();
+    let engine = get_engine_install(fyg_dirs.engines_data(), &version, project.mono)?;
+    if !engine.is_installed() {
+        bail!(
+            "Can't export project. Godot version {} is not installed. Run `fyg install` to install it.",
+            display_version(&version, project.mono),
+        );
+    }
+    project.check_lock(&engine)?;
+    check_display(&version, project.mono)?;
+    if !ExportTemplates::new(&engine, &version, project.mono)?.is_installed() {
+        println!("Installing missing export templates for version {}.", display_version(&version, project.mono));
+        templates::install(&version, project.mono, false, false, &RetryPolicy::default(), config).await?;
+    }
+
+    if let Some(output_dir) = output.parent() {
+        fs::create_dir_all(output_dir)
+            .with_context(|| format!("Could not create {}.", output_dir.display()))?;
+    }
+
+    // Godot 4 runs headless with --headless, and renamed --export to --export-release. Godot 3's
+    // --no-window only works on Windows.
+    let mut command = Command::new(&engine.executable);
+    if version.major >= 4 {
+        command.arg("--headless")
+            .arg(if debug { "--export-debug" } else { "--export-release" });
+    } else {
+        if Platform::get().is_windows() {
+            command.arg("--no-window");
+        }
+        command.arg(if debug { "--export-debug" } else { "--export" });
+    }
+    command.arg(&preset.name)
+        .arg(&output)
+        .arg("--path")
+        .arg(&project.godot_dir);
+
+    // Remember when a previous export was written, so a stale one doesn't pass for this one.
+    let previous_modified = modified_time(&output);
+
+    println!("Exporting {} to {}.", preset.name, output.display());
+    // Godot's output goes straight to ours, so long exports show their progress as they go.
+    let status = command.status()
+        .with_context(|| format!("Could not run {}.", engine.executable.display()))?;
+
+    if !status.success() {
+        match status.code() {
+            Some(code) => bail!("Export failed. Godot exited with code {}.", code),
+            None => bail!("Export failed. Godot was stopped before it finished."),
+        }
+    }
+    // Older versions exit successfully even when the export fails, so check the output changed.
+    let written = match (modified_time(&output), previous_modified) {
+        (Some(modified), Some(previous)) => modified > previous,
+        (modified, None) => modified.is_some(),
+        (None, Some(_)) => false,
+    };
+    if !written {
+        bail!("Export failed. Godot did not write {}.", output.display());
+    }
+
+    println!("Exported to: {}", output.display());
+
+    Ok(())
+}
+
+fn print_preset(preset: &ExportPreset) {
+    if preset.export_path.is_empty() {
+        println!("{} ({})", preset.name, preset.platform);
+    } else {
+        println!("{} ({}): {}", preset.name, preset.platform, preset.export_path);
+    }
+}
+
+/// Fail if the engine can't run here because it needs a display. Godot 3's desktop Linux builds
+/// open a window even when exporting, so machines without a display need its headless build.
+fn check_display(version: &GodotVersion, mono: bool) -> Result<()> {
+    let platform = Platform::get();
+    let needs_display = version.major < 4 && platform.is_linux_desktop();
+    if needs_display && env::var_os("DISPLAY").is_none() {
+        bail!(
+            "Godot {} needs a display to export with its {} build, and DISPLAY isn't set. Use its headless build instead:\n  \
+            fyg install --platform linux-headless\n  \
+            fyg export --platform linux-headless",
+            display_version(version, mono),
+            platform,
+        );
+    }
+    Ok(())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -1,119 +1,240 @@
 use std::{
     fs,
-    io::Write,
+    path::Path,
+    time::SystemTime,
 };
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
 
 use crate::{
-    commands::{get_binary_name, uninstall},
+    checksum,
+    config::Config,
+    download::{self, RetryPolicy},
+    commands::{cache, display_version, get_asset_names, get_engine_dir_name, get_engine_install, templates, uninstall},
     dirs::FygDirs,
-    version::get_full_version,
+    output,
+    platform::Platform,
+    project::Project,
+    sources::{self, Source},
+    version::GodotVersion,
 };
 
-pub async fn cmd(version: &str, force: bool) -> Result<()> {
+pub async fn cmd(
+    version: &GodotVersion,
+    mono: bool,
+    force: bool,
+    redownload: bool,
+    retry: &RetryPolicy,
+    config: &Config,
+    locked_sum: Option<&str>,
+) -> Result<()> {
     let fyg_dirs = FygDirs::get();
-
-    let full_version = get_full_version(version);
-    let bin_name = get_binary_name(&full_version);
-    let bin_path = fyg_dirs.engines_data()
-        .join(&full_version)
-        .join(&bin_name);
-    let zip_name = format!("{}.zip", &bin_name);
+    let client = download::client()?;
+    let platform = Platform::get();
+    let sources = &config.sources();
+
+    let engine_dir_name = get_engine_dir_name(version, mono);
+    let display_version = display_version(version, mono);
+    let engine = get_engine_install(fyg_dirs.engines_data(), version, mono)?;
+    let zip_name = get_asset_names(version, mono)?.zip;
     let zip_path = fyg_dirs.engines_cache()
-        .join(&full_version)
+        .join(&engine_dir_name)
         .join(&zip_name);
 
-    if force {
+    if force && platform.runs_on_host() {
         // Uninstall any existing version before installing.
-        uninstall(fyg_dirs.engines_data(), version)?;
+        uninstall(fyg_dirs.engines_data(), version, mono)?;
     } else {
         // Check if we already have this version installed.
-        if bin_path.is_file() {
-            bail!("Version {} is already installed. Pass --force to re-install.", version);
+        if engine.is_installed() {
+            bail!("Version {} is already installed. Pass --force to re-install.", display_version);
         }
     }
 
-    // Skip download if engine zip is cached.
-    if zip_path.is_file() {
-        // TODO: Check SHA512 sum of zip.
+    let actual_sum = fetch_package(&client, sources, version, mono, &zip_path, redownload, retry, locked_sum)
+        .await?;
 
-        println!("Version {} is already downloaded. Extracting from cache.", version);
+    if let Some(retention_days) = config.cache_retention_days() {
+        cache::prune(retention_days, &engine_dir_name)?;
+    }
 
-        let zip_file = fs::File::open(&zip_path)?;
+    // Builds for other platforms are only useful in the cache, e.g. to pre-stage a shared cache.
+    if !platform.runs_on_host() {
+        println!("Not extracting since builds for {} can't run on this machine.", platform);
+        return Ok(());
+    }
 
-        let data_dir = fyg_dirs.engines_data()
-            .join(&full_version);
-        let mut archive = zip::ZipArchive::new(zip_file)?;
-        archive.extract(&data_dir)?;
+    // Unzip cached file to data dir under its version.
+    engine.extract(&zip_path)?;
+    engine.write_package_sum(&actual_sum)?;
+    if config.self_contained() {
+        engine.create_self_contained_file()?;
+    }
 
-        // By default, add an _sc_ file in the same directory to make Godot use Self-Contained Mode:
-        // https://docs.godotengine.org/en/latest/tutorials/io/data_paths.html#self-contained-mode
-        fs::File::create(data_dir.join("_sc_"))?;
+    println!("Extracted to: {}", engine.dir.to_string_lossy());
 
-        println!("Extracted to: {}", data_dir.to_string_lossy());
+    Ok(())
+}
 
-        return Ok(());
+/// Make sure a release's package is in the cache at `cache_path`, downloading it if needed, and
+/// check it against its checksum. Returns the package's actual SHA-512 sum.
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_package(
+    client: &Client,
+    sources: &[Source],
+    version: &GodotVersion,
+    mono: bool,
+    cache_path: &Path,
+    redownload: bool,
+    retry: &RetryPolicy,
+    locked_sum: Option<&str>,
+) -> Result<String> {
+    let file_name = cache_path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    if cache_path.is_file() && !redownload {
+        // Skip download if the package is cached.
+        println!("{} is already downloaded. Using it from the cache.", file_name);
+
+        // Check the cached package against the lockfile, or else its stored checksum. Fall back
+        // to the release's sums if it was cached before fyg stored checksums.
+        let expected_sum = match (locked_sum.map(str::to_string), checksum::read_stored_sum(cache_path)?) {
+            (Some(sum), _) | (None, Some(sum)) => Some(sum),
+            (None, None) => sources::resolve_package(sources, client, version, mono, &file_name)
+                .await
+                .ok()
+                .and_then(|package| package.expected_sum),
+        };
+        let actual_sum = checksum::sha512_file(cache_path)?;
+        match expected_sum {
+            Some(expected_sum) => {
+                if actual_sum != expected_sum {
+                    bail!(
+                        "Cached {} does not match its {}SHA-512 checksum.\nPass --redownload to download it again.",
+                        cache_path.display(),
+                        if locked_sum.is_some() { "locked " } else { "" },
+                    );
+                }
+                checksum::write_stored_sum(cache_path, &actual_sum)?;
+            }
+            None => print_unverified_warning(&file_name),
+        }
+
+        // Mark the package as used so it isn't pruned from the cache.
+        fs::File::options()
+            .write(true)
+            .open(cache_path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+            .with_context(|| format!("Could not update {}.", cache_path.display()))?;
+
+        return Ok(actual_sum);
     }
 
-    // Try to get the URL for this release.
-    let octocrab = octocrab::instance();
-    let maybe_release = octocrab.repos("godotengine", "godot")
-        .releases()
-        .get_by_tag(&full_version)
-        .await;
-
-    let Ok(release) = maybe_release else {
-        bail!("Version {} not found.", version);
-        // TODO: Get list of releases and print available releases.
-    };
-
-    // Download package for this platform.
-    let maybe_url = release.assets.iter()
-        .find(|asset| asset.name == zip_name)
-        .map(|asset| &asset.browser_download_url);
-    let Some(package_url) = maybe_url else {
-        bail!(
-            "Version {} does not support your platform.\nTuxFamily may have a build available: https://downloads.tuxfamily.org/godotengine/{}/",
-            version,
-            version,
-        );
-    };
-
-    println!("Package URL: {}", package_url);
-
-    // Download the file.
-    let response = reqwest::get(package_url.as_str())
+    // Find the package in the first source that has it.
+    let package = sources::resolve_package(sources, client, version, mono, &file_name)
         .await?;
-    let content = response.bytes()
+    println!("Found {} in {}.", file_name, package.source);
+    if let (Some(locked_sum), Some(published_sum)) = (locked_sum, &package.expected_sum) {
+        if locked_sum != published_sum {
+            bail!(
+                "The published SHA-512 checksum of {} does not match the locked one.\nLocked: {}\nPublished: {}",
+                file_name,
+                locked_sum,
+                published_sum,
+            );
+        }
+    }
+    let expected_sum = locked_sum.map(str::to_string)
+        .or(package.expected_sum);
+    if expected_sum.is_none() {
+        print_unverified_warning(&file_name);
+    }
+
+    println!("Package URL: {}", package.url);
+
+    // Stream the file into the cache directory for versions, checking it before it's kept.
+    if let Some(cache_dir) = cache_path.parent() {
+        fs::create_dir_all(cache_dir)?;
+    }
+    if redownload {
+        // Don't resume an old partial download either.
+        let part_path = download::part_path(cache_path);
+        if part_path.is_file() {
+            fs::remove_file(&part_path)
+                .with_context(|| format!("Could not remove {}.", part_path.display()))?;
+        }
+    }
+    let actual_sum = download::download_file(client, &package.url, cache_path, expected_sum.as_deref(), retry)
         .await?;
+    if expected_sum.is_some() {
+        checksum::write_stored_sum(cache_path, &actual_sum)?;
+    }
 
-    // Copy content to cache directory for versions.
-    let cache_dir = fyg_dirs.engines_cache()
-        .join(&full_version);
-    fs::create_dir_all(&cache_dir)?;
-    let download_path = cache_dir.join(&zip_name);
-    {
-        let mut file = fs::File::create(&download_path)?;
-        file.write_all(&content)?;
+    println!("Downloaded to: {}", cache_path.to_string_lossy());
+    Ok(actual_sum)
+}
+
+/// Install the engine the project containing `start_dir` uses, and its export templates if the
+/// project's config asks for them. Returns the version it resolved to.
+pub async fn cmd_project(
+    start_dir: &Path,
+    mono: bool,
+    force: bool,
+    redownload: bool,
+    retry: &RetryPolicy,
+    config: &Config,
+) -> Result<GodotVersion> {
+    let project = Project::find(start_dir, mono)?;
+    let version = resolve_project_version(&project, &config.sources()).await?;
+    if let (true, Some(config_path)) = (project.export_templates, &project.config_path) {
+        println!("Installing export templates too, since {} asks for them.", config_path.display());
     }
 
-    // TODO: Check SHA512 sum of zip.
+    let engine = get_engine_install(FygDirs::get().engines_data(), &version, project.mono)?;
+    if engine.is_installed() && !force {
+        println!("Version {} is already installed.", display_version(&version, project.mono));
+        project.check_lock(&engine)?;
+    } else {
+        cmd(&version, project.mono, force, redownload, retry, config, project.locked_sum()).await?;
+    }
 
-    println!("Downloaded to: {}", download_path.to_string_lossy());
+    if project.export_templates {
+        templates::install(&version, project.mono, force, redownload, retry, config).await?;
+    }
 
-    // Unzip downloaded file to data dir under its version.
-    let data_dir = fyg_dirs.engines_data()
-        .join(&full_version);
-    let seekable_content = std::io::Cursor::new(content.as_ref());
-    let mut archive = zip::ZipArchive::new(seekable_content)?;
-    archive.extract(&data_dir)?;
+    Ok(version)
+}
 
-    // By default, add an _sc_ file in the same directory to make Godot use Self-Contained Mode:
-    // https://docs.godotengine.org/en/latest/tutorials/io/data_paths.html#self-contained-mode
-    fs::File::create(data_dir.join("_sc_"))?;
+/// Find the version to install for a project. Locked projects use the locked version, and ranges
+/// resolve to the newest release that has a build for this platform.
+async fn resolve_project_version(project: &Project, sources: &[Source]) -> Result<GodotVersion> {
+    if let Some(lock) = project.checked_lock()? {
+        println!("Using version {}.", project.describe_version(&lock.version));
+        return Ok(lock.version.clone());
+    }
 
-    println!("Extracted to: {}", data_dir.to_string_lossy());
+    match project.requirement.exact() {
+        Some(version) => {
+            println!("Using version {}.", project.describe_version(version));
+            Ok(version.clone())
+        }
+        None => {
+            let version = project.newest_release(sources).await?;
+            println!(
+                "Using version {}, the newest release matching {}, from {}.",
+                display_version(&version, project.mono),
+                project.requirement,
+                project.requirement_source,
+            );
+            Ok(version)
+        }
+    }
+}
 
-    Ok(())
+fn print_unverified_warning(file_name: &str) {
+    let warning = format!("Warning: No SHA-512 checksum published for {}. Skipping verification.", file_name);
+    output::warn(&warning);
 }
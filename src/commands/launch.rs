@@ -1,34 +1,79 @@
-use std::process::{Command, Stdio};
+use std::{
+    ffi::OsString,
+    process::{Command, Stdio},
+};
 
 use anyhow::{bail, Result};
 
 use crate::{
-    commands::get_binary_name,
+    commands::{self, display_version, get_engine_install},
     dirs::FygDirs,
-    version::get_full_version,
+    version::GodotVersion,
 };
 
-pub fn cmd(version: &str) -> Result<()> {
+pub fn cmd(version: &GodotVersion, mono: bool, args: &[OsString]) -> Result<()> {
     let fyg_dirs = FygDirs::get();
 
     // Try to launch the specified version.
-    let full_version = get_full_version(version);
-    let bin_name = get_binary_name(&full_version);
-    let bin_path = fyg_dirs.engines_data()
-        .join(&full_version)
-        .join(bin_name);
-
-    if !bin_path.is_file() {
-        bail!("Version {} is not installed.", version);
+    let engine = get_engine_install(fyg_dirs.engines_data(), version, mono)?;
+
+    if !engine.is_installed() {
+        bail!("Version {} is not installed.", display_version(version, mono));
     }
 
-    println!("Running: {}", bin_path.to_string_lossy());
-    Command::new(&bin_path)
-        .arg("--project-manager")
-        .stdin(Stdio::null())
+    println!("Running: {}", engine.executable.to_string_lossy());
+    let mut command = Command::new(&engine.executable);
+    // Open the project manager unless the arguments pick what to open instead.
+    if !picks_what_to_open(args) {
+        command.arg("--project-manager");
+    }
+    if !args.is_empty() {
+        // Godot's output should be visible when it's given arguments, e.g. `--verbose`, so run it
+        // in the foreground.
+        command.args(args);
+        return commands::replace_process(command);
+    }
+    command.stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()?;
 
     Ok(())
 }
+
+/// Whether `args` tell Godot what to open, like a project's path or a scene, or already pick the
+/// project manager.
+fn picks_what_to_open(args: &[OsString]) -> bool {
+    args.iter().any(|arg| {
+        let arg = arg.to_string_lossy();
+        matches!(arg.as_ref(), "--path" | "--main-pack" | "-e" | "--editor" | "-p" | "--project-manager")
+            || arg.starts_with("res://")
+            || [".tscn", ".scn", ".godot", ".pck"].iter().any(|extension| arg.ends_with(extension))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picks(args: &[&str]) -> bool {
+        let args = args.iter().map(OsString::from).collect::<Vec<_>>();
+        picks_what_to_open(&args)
+    }
+
+    #[test]
+    fn keeps_project_manager_for_options() {
+        assert!(!picks(&[]));
+        assert!(!picks(&["--verbose"]));
+        assert!(!picks(&["--rendering-driver", "opengl3"]));
+    }
+
+    #[test]
+    fn skips_project_manager_for_paths_and_scenes() {
+        assert!(picks(&["--path", "/games/demo"]));
+        assert!(picks(&["/games/demo/project.godot"]));
+        assert!(picks(&["--verbose", "res://levels/level_1.tscn"]));
+        assert!(picks(&["--main-pack", "demo.pck"]));
+        assert!(picks(&["-e"]));
+    }
+}
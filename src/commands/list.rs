@@ -1,94 +1,321 @@
-use std::fs;
+use std::{
+    fs,
+    path::PathBuf,
+};
 
 use anyhow::Result;
-use octocrab::models::repos::Release;
-use owo_colors::OwoColorize;
+use serde::Serialize;
 
 use crate::{
-    commands::get_binary_name,
+    commands::{display_version, Variant, get_asset_names, get_engine_dir_name, get_engine_install, parse_engine_dir_name},
     dirs::FygDirs,
-    version::get_full_version,
+    output,
+    platform::Platform,
+    sources::{self, AssetInfo, Source},
+    version::{GodotVersion, VersionPrefix},
 };
 
+/// Which available versions to list.
+pub struct ListFilter {
+    /// Include pre-release versions.
+    pub prerelease: bool,
+    /// Only versions starting with this, e.g. `4.2`.
+    pub prefix: Option<VersionPrefix>,
+    /// Only versions this one or newer.
+    pub since: Option<GodotVersion>,
+    /// Only the newest patch release of each minor version.
+    pub latest: bool,
+}
+
+impl ListFilter {
+    fn matches(&self, version: &GodotVersion) -> bool {
+        self.prefix.as_ref().map_or(true, |prefix| prefix.matches(version)) &&
+            self.since.as_ref().map_or(true, |since| version >= since)
+    }
+}
+
 #[must_use]
-fn is_installed(version: &str, fyg_dirs: &FygDirs) -> bool {
-    let full_version = get_full_version(version);
-    let bin_name = get_binary_name(&full_version);
-    let bin_path = fyg_dirs.engines_data()
-        .join(&full_version)
-        .join(&bin_name);
-    bin_path.is_file()
+fn is_installed(version: &GodotVersion, mono: bool, fyg_dirs: &FygDirs) -> bool {
+    get_engine_install(fyg_dirs.engines_data(), version, mono)
+        .is_ok_and(|engine| engine.is_installed())
 }
 
-pub async fn cmd(available: bool) -> Result<()> {
-    let fyg_dirs = FygDirs::get();
+/// Whether this platform's package for a version is in the download cache.
+#[must_use]
+fn is_cached(version: &GodotVersion, mono: bool, zip_name: &str, fyg_dirs: &FygDirs) -> bool {
+    fyg_dirs.engines_cache()
+        .join(get_engine_dir_name(version, mono))
+        .join(zip_name)
+        .is_file()
+}
 
-    if !available {
-        if !fyg_dirs.engines_data().is_dir() {
-            // Engines directory doesn't exist, so no engines installed.
-            return Ok(());
-        }
+/// An installed version, as shown in JSON output.
+#[derive(Serialize)]
+struct InstalledVersion {
+    version: GodotVersion,
+    variant: Variant,
+    /// Directory the engine is installed in.
+    path: PathBuf,
+    executable: PathBuf,
+    self_contained: bool,
+}
 
+/// A version available to install, as shown in JSON output.
+#[derive(Serialize)]
+struct AvailableVersion {
+    version: GodotVersion,
+    variant: Variant,
+    published_at: Option<String>,
+    /// Whether there's a build for this platform.
+    supported: bool,
+    installed: bool,
+    cached: bool,
+    /// Whether there's a Mono build for this platform. Always false when listing Mono versions.
+    mono_available: bool,
+    /// This platform's package, if there's a build for it.
+    package: Option<AssetInfo>,
+}
+
+/// Everything `list` prints as JSON.
+#[derive(Serialize)]
+struct VersionList<T> {
+    /// The platform builds are for, e.g. `linux-64`.
+    platform: String,
+    versions: Vec<T>,
+}
+
+pub async fn cmd(available: bool, filter: &ListFilter, mono: bool, sources: &[Source]) -> Result<()> {
+    if available {
+        list_available(filter, mono, sources).await
+    } else {
+        list_installed(mono)
+    }
+}
+
+fn list_installed(mono: bool) -> Result<()> {
+    let fyg_dirs = FygDirs::get();
+
+    let mut versions = Vec::new();
+    if fyg_dirs.engines_data().is_dir() {
         // Start by finding the installed versions.
         let read_dir = fs::read_dir(fyg_dirs.engines_data())?;
-        // By default, list just the installed versions.
         for entry in read_dir {
             let entry = entry?;
             let version_path = entry.path();
             if version_path.is_dir() {
                 let file_name = entry.file_name();
-                let full_version = file_name.to_string_lossy();
-                let bin_name = get_binary_name(&full_version);
-                let bin_path = fyg_dirs.engines_data()
-                    .join(full_version.as_ref())
-                    .join(bin_name);
+                let engine_dir_name = file_name.to_string_lossy();
+                let Some((version, is_mono)) = parse_engine_dir_name(&engine_dir_name) else {
+                    continue;
+                };
+                if mono && !is_mono {
+                    continue;
+                }
                 // TODO: Also check that it's executable?
-                if bin_path.is_file() {
-                    let version = full_version.strip_suffix("-stable")
-                        .unwrap_or(&full_version);
-                    println!("{}", &version);
+                let Ok(engine) = get_engine_install(fyg_dirs.engines_data(), &version, is_mono) else {
+                    continue;
+                };
+                if engine.is_installed() {
+                    versions.push(InstalledVersion {
+                        self_contained: engine.is_self_contained(),
+                        version,
+                        variant: Variant::new(is_mono),
+                        path: engine.dir,
+                        executable: engine.executable,
+                    });
                 }
             }
         }
+    }
+    versions.sort_by(|a, b| b.version.cmp(&a.version));
 
-        return Ok(());
+    if output::is_json() {
+        return output::print_json(&VersionList {
+            platform: Platform::get().name(),
+            versions,
+        });
     }
+    for installed in &versions {
+        println!("{}", display_version(&installed.version, installed.variant == Variant::Mono));
+    }
+
+    Ok(())
+}
+
+async fn list_available(filter: &ListFilter, mono: bool, sources: &[Source]) -> Result<()> {
+    let fyg_dirs = FygDirs::get();
 
-    // Query GitHub for list of Godot Releases.
-    let octocrab = octocrab::instance();
-    let mut page = octocrab.repos("godotengine", "godot")
-        .releases()
-        .list()
-        .per_page(100)
-        .send()
-        .await?;
-
-    // List release versions.
-    // TODO: Filter out/mark ones that don't support this platform.
-    // TODO: Add option for ones with mono versions.
-    // TODO: Sort by version number.
-    loop {
-        // List versions on this page.
-        for release in &page.items {
-            let release_version = release.tag_name.strip_suffix("-stable")
-                .unwrap_or(&release.tag_name);
-            if is_installed(release_version, &fyg_dirs) {
-                let installed = format!("{} (installed)", release_version);
-                println!("{}", installed.bold());
-            } else {
-                println!("{}", release_version);
+    // Query the configured sources for list of Godot Releases.
+    let mut releases = sources::list_releases(sources, filter.prerelease).await?;
+    releases.retain(|release| filter.matches(&release.version));
+    if mono {
+        // Only show releases that have Mono builds, though maybe not for this platform.
+        releases.retain(|release| release.assets.iter().any(|asset| asset.name.contains("_mono_")));
+    }
+
+    // Newest first, so the newest patch release leads each minor version.
+    releases.sort_by(|a, b| b.version.cmp(&a.version));
+    releases.dedup_by(|a, b| a.version == b.version);
+    if filter.latest {
+        releases.dedup_by_key(|release| (release.version.major, release.version.minor));
+    }
+
+    let versions = releases.into_iter()
+        .map(|release| {
+            let version = &release.version;
+            let package = get_asset_names(version, mono)
+                .ok()
+                .and_then(|asset_names| release.asset(&asset_names.zip).cloned());
+            let mono_available = !mono && get_asset_names(version, true)
+                .is_ok_and(|mono_names| release.has_asset(&mono_names.zip));
+            AvailableVersion {
+                supported: package.is_some(),
+                installed: package.is_some() && is_installed(version, mono, fyg_dirs),
+                cached: package.as_ref().is_some_and(|package| is_cached(version, mono, &package.name, fyg_dirs)),
+                mono_available,
+                variant: Variant::new(mono),
+                published_at: release.published_at,
+                package,
+                version: release.version,
             }
+        })
+        .collect::<Vec<_>>();
+
+    if output::is_json() {
+        return output::print_json(&VersionList {
+            platform: Platform::get().name(),
+            versions,
+        });
+    }
+
+    let mut minor_version = None;
+    for available in &versions {
+        let version = &available.version;
+        if minor_version != Some((version.major, version.minor)) {
+            minor_version = Some((version.major, version.minor));
+            println!("{}", output::bold(&format!("{}.{}", version.major, version.minor)));
+        }
+
+        let release_version = display_version(version, mono);
+        if !available.supported {
+            let unsupported = format!("{} (no build for {})", release_version, Platform::get());
+            println!("  {}", output::dimmed(&unsupported));
+            continue;
         }
 
-        // Try to get the next page, if any.
-        page = match octocrab
-            .get_page::<Release>(&page.next)
-            .await?
-        {
-            Some(next_page) => next_page,
-            None => break,
+        let markers = [
+            (available.installed, "installed"),
+            (available.cached, "cached"),
+            (available.mono_available, "mono available"),
+        ];
+        let markers = markers.iter()
+            .filter(|(is_marked, _)| *is_marked)
+            .map(|(_, marker)| *marker)
+            .collect::<Vec<_>>();
+        let line = if markers.is_empty() {
+            release_version
+        } else {
+            format!("{} ({})", release_version, markers.join(", "))
+        };
+        if available.installed {
+            println!("  {}", output::bold(&line));
+        } else {
+            println!("  {}", line);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn installed_json_schema() {
+        let list = VersionList {
+            platform: Platform::Linux64.name(),
+            versions: vec![InstalledVersion {
+                version: "4.2.1".parse().unwrap(),
+                variant: Variant::Mono,
+                path: PathBuf::from("/engines/4.2.1-stable_mono"),
+                executable: PathBuf::from("/engines/4.2.1-stable_mono/Godot"),
+                self_contained: true,
+            }],
+        };
+        assert_eq!(serde_json::to_value(&list).unwrap(), json!({
+            "platform": "linux-64",
+            "versions": [{
+                "version": "4.2.1-stable",
+                "variant": "mono",
+                "path": "/engines/4.2.1-stable_mono",
+                "executable": "/engines/4.2.1-stable_mono/Godot",
+                "self_contained": true,
+            }],
+        }));
+    }
+
+    #[test]
+    fn available_json_schema() {
+        let list = VersionList {
+            platform: Platform::Linux64.name(),
+            versions: vec![
+                AvailableVersion {
+                    version: "4.3-beta2".parse().unwrap(),
+                    variant: Variant::Standard,
+                    published_at: Some(String::from("2024-06-14T00:00:00+00:00")),
+                    supported: true,
+                    installed: false,
+                    cached: true,
+                    mono_available: true,
+                    package: Some(AssetInfo {
+                        name: String::from("Godot_v4.3-beta2_linux.x86_64.zip"),
+                        url: String::from("https://example.com/Godot_v4.3-beta2_linux.x86_64.zip"),
+                        size: 1234,
+                    }),
+                },
+                AvailableVersion {
+                    version: "4.1".parse().unwrap(),
+                    variant: Variant::Standard,
+                    published_at: None,
+                    supported: false,
+                    installed: false,
+                    cached: false,
+                    mono_available: false,
+                    package: None,
+                },
+            ],
+        };
+        assert_eq!(serde_json::to_value(&list).unwrap(), json!({
+            "platform": "linux-64",
+            "versions": [
+                {
+                    "version": "4.3-beta2",
+                    "variant": "standard",
+                    "published_at": "2024-06-14T00:00:00+00:00",
+                    "supported": true,
+                    "installed": false,
+                    "cached": true,
+                    "mono_available": true,
+                    "package": {
+                        "name": "Godot_v4.3-beta2_linux.x86_64.zip",
+                        "url": "https://example.com/Godot_v4.3-beta2_linux.x86_64.zip",
+                        "size": 1234,
+                    },
+                },
+                {
+                    "version": "4.1-stable",
+                    "variant": "standard",
+                    "published_at": null,
+                    "supported": false,
+                    "installed": false,
+                    "cached": false,
+                    "mono_available": false,
+                    "package": null,
+                },
+            ],
+        }));
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::{
+    assets, checksum,
+    commands::display_version,
+    download,
+    lock::{LockedAsset, Lockfile},
+    output,
+    platform::Platform,
+    project::Project,
+    sources::{self, Source},
+};
+
+pub async fn cmd(start_dir: &Path, mono: bool, sources: &[Source]) -> Result<()> {
+    let project = Project::find(start_dir, mono)?;
+    let Some(lock_path) = &project.lock_path else {
+        bail!("Can't lock a project without a config file. Add a fyg.toml with the version to use.");
+    };
+
+    let version = match project.requirement.exact() {
+        Some(version) => version.clone(),
+        None => project.newest_release(sources).await?,
+    };
+    let display_version = display_version(&version, project.mono);
+
+    let client = download::client()?;
+    let sums = sources::fetch_sums(sources, &client, &version, project.mono).await?;
+    if sums.is_none() {
+        let warning = format!(
+            "Warning: No SHA-512 checksums published for version {}. Only locking asset names.",
+            display_version,
+        );
+        output::warn(&warning);
+    }
+
+    let assets = Platform::ALL.iter()
+        .filter_map(|&platform| {
+            let asset_names = assets::resolve(&version, platform, project.mono)?;
+            let sha512 = sums.as_deref()
+                .and_then(|sums| checksum::find_sum(sums, &asset_names.zip));
+            // When the release lists its files' sums, only lock the zips it actually published.
+            if sums.is_some() && sha512.is_none() {
+                return None;
+            }
+            Some(LockedAsset {
+                platform: platform.name(),
+                name: asset_names.zip,
+                sha512,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let lockfile = Lockfile {
+        version,
+        mono: project.mono,
+        assets,
+    };
+    lockfile.save(lock_path)?;
+
+    println!("Locked version {} in {}.", display_version, lock_path.display());
+    for asset in &lockfile.assets {
+        println!("  {}: {}", asset.platform, asset.name);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    commands::{get_engine_install, Variant},
+    dirs::FygDirs,
+    output,
+    project::Project,
+    version::GodotVersion,
+};
+
+/// A project, as shown in JSON output.
+#[derive(Serialize)]
+struct ProjectInfo {
+    /// The project's fyg config. `None` if its version came from project.godot.
+    config: Option<PathBuf>,
+    /// Directory holding project.godot.
+    root: PathBuf,
+    /// Which versions the project can use, e.g. `~4.2` or `4.2.1`.
+    requirement: String,
+    requirement_source: String,
+    variant: Variant,
+    /// The project's lockfile, if it's been locked.
+    lock: Option<PathBuf>,
+    /// The version the project uses, which may not be installed if it's locked or exact. `None`
+    /// for a range no installed version matches.
+    version: Option<GodotVersion>,
+    installed: bool,
+    /// The engine's install dir and executable, when the version is installed.
+    path: Option<PathBuf>,
+    executable: Option<PathBuf>,
+}
+
+pub fn cmd(start_dir: &Path, mono: bool) -> Result<()> {
+    let project = Project::find(start_dir, mono)?;
+
+    if output::is_json() {
+        let version = project.installed_version().ok();
+        let engine = version.as_ref()
+            .and_then(|version| get_engine_install(FygDirs::get().engines_data(), version, project.mono).ok())
+            .filter(|engine| engine.is_installed());
+        return output::print_json(&ProjectInfo {
+            config: project.config_path.clone(),
+            root: project.godot_dir.clone(),
+            requirement: project.requirement.to_string(),
+            requirement_source: project.requirement_source.clone(),
+            variant: Variant::new(project.mono),
+            lock: project.lock.as_ref().and(project.lock_path.clone()),
+            version,
+            installed: engine.is_some(),
+            path: engine.as_ref().map(|engine| engine.dir.clone()),
+            executable: engine.map(|engine| engine.executable),
+        });
+    }
+
+    match &project.config_path {
+        Some(config_path) => println!("Config: {}", config_path.display()),
+        None => println!("Config: none"),
+    }
+    println!("Root: {}", project.godot_dir.display());
+    match project.installed_version() {
+        Ok(version) => println!("Version: {}", project.describe_version(&version)),
+        Err(_) => println!(
+            "Version: {}, from {} (no matching version installed)",
+            project.requirement,
+            project.requirement_source,
+        ),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn json_schema() {
+        let info = ProjectInfo {
+            config: Some(PathBuf::from("/game/fyg.toml")),
+            root: PathBuf::from("/game/godot"),
+            requirement: String::from("~4.2"),
+            requirement_source: String::from("/game/fyg.toml"),
+            variant: Variant::Standard,
+            lock: None,
+            version: None,
+            installed: false,
+            path: None,
+            executable: None,
+        };
+        assert_eq!(serde_json::to_value(&info).unwrap(), json!({
+            "config": "/game/fyg.toml",
+            "root": "/game/godot",
+            "requirement": "~4.2",
+            "requirement_source": "/game/fyg.toml",
+            "variant": "standard",
+            "lock": null,
+            "version": null,
+            "installed": false,
+            "path": null,
+            "executable": null,
+        }));
+    }
+}
@@ -0,0 +1,25 @@
+use std::{
+    ffi::OsString,
+    path::Path,
+    process::Command,
+};
+
+use anyhow::Result;
+
+use crate::{
+    commands::{get_project_engine, replace_process},
+    config::Config,
+};
+
+/// Play the project's main scene, or a scene passed through in `args`, showing Godot's output
+/// and exiting with its exit code.
+pub async fn cmd(project_fyg_dir: &Path, mono: bool, args: &[OsString], config: &Config) -> Result<()> {
+    let (project, engine) = get_project_engine(project_fyg_dir, mono, config, "run").await?;
+
+    println!("Running project with: {}", engine.executable.to_string_lossy());
+    let mut command = Command::new(&engine.executable);
+    command.arg("--path")
+        .arg(&project.godot_dir)
+        .args(args);
+    replace_process(command)
+}
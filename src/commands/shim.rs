@@ -0,0 +1,139 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::Path,
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    commands::{self, display_version, get_engine_install, installed_versions},
+    config::{Config, Settings},
+    dirs::FygDirs,
+    output,
+    project::Project,
+    version::GodotVersion,
+};
+
+/// Names fyg acts as a shim under, and the major version each one runs.
+const SHIMS: &[(&str, Option<u32>)] = &[
+    ("godot", None),
+    ("godot4", Some(4)),
+    ("godot3", Some(3)),
+];
+
+/// If fyg was run as one of its shims, e.g. through a `godot` link, find the major version that
+/// shim runs. Returns `None` when run as fyg itself.
+pub fn from_exe_name(exe_name: &Path) -> Option<Option<u32>> {
+    let stem = exe_name.file_stem()?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    SHIMS.iter()
+        .find(|(name, _)| *name == stem)
+        .map(|&(_, major)| major)
+}
+
+pub fn cmd() -> Result<()> {
+    create_shims()?;
+    let bin_dir = FygDirs::get().bin();
+    println!("Created godot, godot4, and godot3 shims in {}.", bin_dir.display());
+    if !bin_dir_in_path() {
+        println!("Add {} to your PATH to run them from anywhere.", bin_dir.display());
+    }
+
+    Ok(())
+}
+
+/// Create the shims in fyg's bin dir, replacing any from an older fyg. On Unix these are symlinks
+/// to fyg, and elsewhere they're copies.
+fn create_shims() -> Result<()> {
+    let bin_dir = FygDirs::get().bin();
+    fs::create_dir_all(bin_dir)
+        .with_context(|| format!("Could not create {}.", bin_dir.display()))?;
+    let fyg_exe = env::current_exe()
+        .context("Could not find the fyg executable.")?;
+
+    for (name, _) in SHIMS {
+        let shim_path = bin_dir.join(name)
+            .with_extension(env::consts::EXE_EXTENSION);
+        if shim_path.symlink_metadata().is_ok() {
+            fs::remove_file(&shim_path)
+                .with_context(|| format!("Could not remove old shim {}.", shim_path.display()))?;
+        }
+
+        #[cfg(unix)]
+        let result = std::os::unix::fs::symlink(&fyg_exe, &shim_path);
+        #[cfg(not(unix))]
+        let result = fs::copy(&fyg_exe, &shim_path).map(|_| ());
+        result.with_context(|| format!("Could not create shim {}.", shim_path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Whether the user can run the shims without typing their full path.
+fn bin_dir_in_path() -> bool {
+    let bin_dir = FygDirs::get().bin();
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir == bin_dir))
+}
+
+/// Run the engine for the current project with `args`, or the default version outside of a
+/// project. `major` restricts which versions can run, e.g. to 4 for the `godot4` shim.
+pub fn exec(major: Option<u32>, args: Vec<OsString>) -> Result<()> {
+    let fyg_dirs = FygDirs::get();
+    let current_dir = env::current_dir()?;
+    let project = Project::discover(&current_dir, false)?;
+    let config = Config::load(Settings::default(), &current_dir)?;
+    for warning in config.warnings() {
+        output::warn(warning);
+    }
+
+    let mono = project.as_ref()
+        .is_some_and(|project| project.mono);
+    let matches_major = |version: &GodotVersion| major.map_or(true, |major| version.major == major);
+
+    // Prefer the project's version, then the default version, then the newest install with the
+    // shim's major version.
+    let project_version = match &project {
+        Some(project) if major.is_none() => Some(project.installed_version()?),
+        // A project for another major version shouldn't stop e.g. `godot3` from running.
+        Some(project) => project.installed_version().ok(),
+        None => None,
+    };
+    let candidates = [
+        project_version,
+        config.default_version(),
+    ];
+    let version = match candidates.into_iter().flatten().find(matches_major) {
+        Some(version) => version,
+        None => {
+            let newest = major.and_then(|_| installed_versions(fyg_dirs.engines_data(), mono)
+                .ok()?
+                .into_iter()
+                .find(matches_major));
+            match (newest, major) {
+                (Some(version), _) => version,
+                (None, Some(major)) => bail!("No Godot {} version is installed.", major),
+                (None, None) => bail!(
+                    "No project config found and no default version set. Set one with `fyg default <version>`.",
+                ),
+            }
+        }
+    };
+
+    let engine = get_engine_install(fyg_dirs.engines_data(), &version, mono)?;
+    if !engine.is_installed() {
+        bail!(
+            "Version {} is not installed. Install it with `fyg install {}`.",
+            display_version(&version, mono),
+            version,
+        );
+    }
+
+    let mut command = Command::new(&engine.executable);
+    command.args(args);
+    commands::replace_process(command)
+}
@@ -0,0 +1,104 @@
+use anyhow::{bail, Result};
+
+use crate::{
+    assets,
+    cli::TemplatesCommand,
+    commands::{cache, display_version, get_engine_dir_name, get_engine_install, install, installed_versions},
+    config::Config,
+    dirs::FygDirs,
+    download::{self, RetryPolicy},
+    platform::Platform,
+    templates::ExportTemplates,
+    version::GodotVersion,
+};
+
+pub async fn cmd(templates_command: &TemplatesCommand, config: &Config) -> Result<()> {
+    let fyg_dirs = FygDirs::get();
+
+    match templates_command {
+        TemplatesCommand::Install { version, mono, force, redownload, attempts } => {
+            let retry = RetryPolicy::with_attempts(*attempts);
+            install(version, *mono, *force, *redownload, &retry, config).await?;
+        }
+        TemplatesCommand::List { mono } => {
+            for version in installed_versions(fyg_dirs.engines_data(), *mono)? {
+                let engine = get_engine_install(fyg_dirs.engines_data(), &version, *mono)?;
+                let templates = ExportTemplates::new(&engine, &version, *mono)?;
+                if templates.is_installed() {
+                    println!("{}: {}", display_version(&version, *mono), templates.dir.display());
+                }
+            }
+        }
+        TemplatesCommand::Rm { version, mono } => {
+            let display_version = display_version(version, *mono);
+            let engine = get_engine_install(fyg_dirs.engines_data(), version, *mono)?;
+            if !engine.is_installed() {
+                bail!("Version {} is not installed.", display_version);
+            }
+            let templates = ExportTemplates::new(&engine, version, *mono)?;
+            if !templates.is_installed() {
+                bail!("Export templates for version {} are not installed.", display_version);
+            }
+            templates.remove()?;
+            println!("Removed export templates for version {} from {}.", display_version, templates.dir.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Install the export templates for an installed engine version where its editor looks for them.
+pub async fn install(
+    version: &GodotVersion,
+    mono: bool,
+    force: bool,
+    redownload: bool,
+    retry: &RetryPolicy,
+    config: &Config,
+) -> Result<()> {
+    let fyg_dirs = FygDirs::get();
+    let platform = Platform::get();
+    let display_version = display_version(version, mono);
+    let Some(tpz_name) = assets::templates(version, mono) else {
+        bail!("Version {} has no export templates.", display_version);
+    };
+
+    // Where templates go depends on how the editor was installed, so it has to be installed
+    // first. Builds for other platforms are only cached.
+    let templates = if platform.runs_on_host() {
+        let engine = get_engine_install(fyg_dirs.engines_data(), version, mono)?;
+        if !engine.is_installed() {
+            bail!("Version {} is not installed. Install it before its export templates.", display_version);
+        }
+        let templates = ExportTemplates::new(&engine, version, mono)?;
+        if templates.is_installed() && !force {
+            println!("Export templates for version {} are already installed.", display_version);
+            return Ok(());
+        }
+        Some(templates)
+    } else {
+        None
+    };
+
+    let engine_dir_name = get_engine_dir_name(version, mono);
+    let tpz_path = fyg_dirs.engines_cache()
+        .join(&engine_dir_name)
+        .join(&tpz_name);
+    let client = download::client()?;
+    install::fetch_package(&client, &config.sources(), version, mono, &tpz_path, redownload, retry, None)
+        .await?;
+
+    if let Some(retention_days) = config.cache_retention_days() {
+        cache::prune(retention_days, &engine_dir_name)?;
+    }
+
+    let Some(templates) = templates else {
+        println!("Not extracting since builds for {} can't run on this machine.", platform);
+        return Ok(());
+    };
+    templates.extract(&tpz_path)?;
+
+    println!("Extracted export templates to: {}", templates.dir.to_string_lossy());
+
+    Ok(())
+}
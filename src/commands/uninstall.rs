@@ -1,15 +1,16 @@
 use anyhow::Result;
 
 use crate::{
-    commands::uninstall,
+    commands::{display_version, uninstall},
     dirs::FygDirs,
+    version::GodotVersion,
 };
 
-pub fn cmd(version: &str) -> Result<()> {
+pub fn cmd(version: &GodotVersion, mono: bool) -> Result<()> {
     let fyg_dirs = FygDirs::get();
 
-    uninstall(fyg_dirs.engines_data(), version)?;
-    println!("Uninstalled version {}.", version);
+    uninstall(fyg_dirs.engines_data(), version, mono)?;
+    println!("Uninstalled version {}.", display_version(version, mono));
 
     Ok(())
 }
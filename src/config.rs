@@ -1,36 +1,568 @@
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{bail, Result, Context};
+use anyhow::{anyhow, bail, Error, Result, Context};
+use clap::ValueEnum;
 use serde::Deserialize;
 
-static PROJECT_FYG_CONFIGS: &[&str] = &[
+use crate::{
+    dirs::FygDirs,
+    output::ColorChoice,
+    release_index,
+    sources::Source,
+    version::{GodotVersion, VersionReq},
+};
+
+static USER_FYG_CONFIG: &str = "config.toml";
+
+/// Shown in place of secret settings like tokens.
+pub const HIDDEN_VALUE: &str = "(hidden)";
+
+pub static PROJECT_FYG_CONFIGS: &[&str] = &[
     "fyg.toml",
     "godot_version.toml",
 ];
 
+/// Settings that can come from the user's config, a project's config, environment variables, or
+/// command line flags. Unset settings fall through to the next layer, and finally to defaults.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Version to use when a command isn't given one, e.g. `launch`.
+    pub default_version: Option<GodotVersion>,
+    /// Whether installed engines keep their settings and data alongside the engine.
+    pub self_contained: Option<bool>,
+    /// Remove cached downloads that haven't been used in this many days.
+    pub cache_retention_days: Option<u32>,
+    /// Whether `edit` and `run` install a missing engine without asking.
+    pub auto_install: Option<bool>,
+    /// Never go online, working only from the cached release index and downloads.
+    pub offline: Option<bool>,
+    /// How many hours the cached release index is used before it's fetched again.
+    pub release_index_ttl_hours: Option<u32>,
+    /// Token for GitHub's API, which raises its rate limit.
+    pub github_token: Option<String>,
+    /// When to color output.
+    pub color: Option<ColorChoice>,
+    /// Where to download engines from, in order of preference.
+    pub sources: Option<Vec<Source>>,
+}
+
+impl Settings {
+    /// Remove settings a project's config can't be trusted with. Project configs are usually
+    /// committed, and come with whatever repo was cloned, so they can't hold secrets or send the
+    /// user's GitHub token to another host. Returns a warning for each setting removed.
+    fn remove_untrusted(&mut self, project_config_path: &Path) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.github_token.take().is_some() {
+            warnings.push(format!(
+                "Warning: Ignoring github_token in {}. Set it in your user config or GITHUB_TOKEN instead.",
+                project_config_path.display(),
+            ));
+        }
+        if let Some(sources) = &mut self.sources {
+            sources.retain(|source| {
+                if !source.is_custom_github() {
+                    return true;
+                }
+                warnings.push(format!(
+                    "Warning: Ignoring source {} in {}. GitHub sources with an api_url can only be set in your user config.",
+                    source,
+                    project_config_path.display(),
+                ));
+                false
+            });
+        }
+        warnings
+    }
+}
+
+/// A setting's name, as written in config files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ConfigKey {
+    #[value(name = "default_version")]
+    DefaultVersion,
+    #[value(name = "self_contained")]
+    SelfContained,
+    #[value(name = "cache_retention_days")]
+    CacheRetentionDays,
+    #[value(name = "auto_install")]
+    AutoInstall,
+    #[value(name = "offline")]
+    Offline,
+    #[value(name = "release_index_ttl_hours")]
+    ReleaseIndexTtlHours,
+    #[value(name = "github_token")]
+    GithubToken,
+    #[value(name = "color")]
+    Color,
+    #[value(name = "sources")]
+    Sources,
+}
+
+impl ConfigKey {
+    pub const ALL: &'static [ConfigKey] = &[
+        ConfigKey::DefaultVersion,
+        ConfigKey::SelfContained,
+        ConfigKey::CacheRetentionDays,
+        ConfigKey::AutoInstall,
+        ConfigKey::Offline,
+        ConfigKey::ReleaseIndexTtlHours,
+        ConfigKey::GithubToken,
+        ConfigKey::Color,
+        ConfigKey::Sources,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ConfigKey::DefaultVersion => "default_version",
+            ConfigKey::SelfContained => "self_contained",
+            ConfigKey::CacheRetentionDays => "cache_retention_days",
+            ConfigKey::AutoInstall => "auto_install",
+            ConfigKey::Offline => "offline",
+            ConfigKey::ReleaseIndexTtlHours => "release_index_ttl_hours",
+            ConfigKey::GithubToken => "github_token",
+            ConfigKey::Color => "color",
+            ConfigKey::Sources => "sources",
+        }
+    }
+
+    /// Whether the setting's value is a secret that's never shown.
+    pub fn is_secret(self) -> bool {
+        self == ConfigKey::GithubToken
+    }
+
+    /// The environment variables that override this setting, in order of precedence.
+    fn env_vars(self) -> &'static [&'static str] {
+        match self {
+            ConfigKey::DefaultVersion => &["FYG_DEFAULT_VERSION"],
+            ConfigKey::SelfContained => &["FYG_SELF_CONTAINED"],
+            ConfigKey::CacheRetentionDays => &["FYG_CACHE_RETENTION_DAYS"],
+            ConfigKey::AutoInstall => &["FYG_AUTO_INSTALL"],
+            ConfigKey::Offline => &["FYG_OFFLINE"],
+            ConfigKey::ReleaseIndexTtlHours => &["FYG_RELEASE_INDEX_TTL_HOURS"],
+            ConfigKey::GithubToken => &["FYG_GITHUB_TOKEN", "GITHUB_TOKEN"],
+            ConfigKey::Color => &["FYG_COLOR"],
+            ConfigKey::Sources => &[],
+        }
+    }
+
+    /// The first of this setting's environment variables that's set, and its value.
+    fn env_value(self) -> Option<(&'static str, String)> {
+        self.env_vars()
+            .iter()
+            .find_map(|&env_var| env::var(env_var)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .map(|value| (env_var, value)))
+    }
+
+    /// Parse a value from the command line or an environment variable into `settings`.
+    fn parse_into(self, value: &str, settings: &mut Settings) -> Result<()> {
+        match self {
+            ConfigKey::DefaultVersion => settings.default_version = Some(value.parse()?),
+            ConfigKey::SelfContained => settings.self_contained = Some(parse_bool(value)?),
+            ConfigKey::CacheRetentionDays => {
+                let days = value.parse()
+                    .with_context(|| format!("Expected a number of days, got \"{}\".", value))?;
+                settings.cache_retention_days = Some(days);
+            }
+            ConfigKey::AutoInstall => settings.auto_install = Some(parse_bool(value)?),
+            ConfigKey::Offline => settings.offline = Some(parse_bool(value)?),
+            ConfigKey::ReleaseIndexTtlHours => {
+                let hours = value.parse()
+                    .with_context(|| format!("Expected a number of hours, got \"{}\".", value))?;
+                settings.release_index_ttl_hours = Some(hours);
+            }
+            ConfigKey::GithubToken => settings.github_token = Some(value.to_string()),
+            ConfigKey::Color => {
+                let color = ColorChoice::from_str(value, true)
+                    .map_err(|_| anyhow!("Expected one of \"auto\", \"always\", or \"never\", got \"{}\".", value))?;
+                settings.color = Some(color);
+            }
+            ConfigKey::Sources => bail!("Sources can only be set in a config file."),
+        }
+        Ok(())
+    }
+
+    /// Format this setting's value in `settings` for display, if it's set.
+    fn display(self, settings: &Settings) -> Option<String> {
+        match self {
+            ConfigKey::DefaultVersion => settings.default_version.as_ref().map(GodotVersion::to_string),
+            ConfigKey::SelfContained => settings.self_contained.map(|value| value.to_string()),
+            ConfigKey::CacheRetentionDays => settings.cache_retention_days.map(|days| days.to_string()),
+            ConfigKey::AutoInstall => settings.auto_install.map(|value| value.to_string()),
+            ConfigKey::Offline => settings.offline.map(|value| value.to_string()),
+            ConfigKey::ReleaseIndexTtlHours => settings.release_index_ttl_hours.map(|hours| hours.to_string()),
+            // Never show the token, so it can't leak into logs.
+            ConfigKey::GithubToken => settings.github_token.as_ref().map(|_| HIDDEN_VALUE.to_string()),
+            ConfigKey::Color => settings.color.map(color_name),
+            ConfigKey::Sources => settings.sources.as_ref().map(|sources| display_sources(sources)),
+        }
+    }
+
+    /// Format this setting's default value for display, if it has one.
+    fn display_default(self) -> Option<String> {
+        match self {
+            ConfigKey::DefaultVersion |
+            ConfigKey::CacheRetentionDays |
+            ConfigKey::AutoInstall |
+            ConfigKey::GithubToken => None,
+            ConfigKey::SelfContained => Some(true.to_string()),
+            ConfigKey::Offline => Some(false.to_string()),
+            ConfigKey::ReleaseIndexTtlHours => Some(release_index::DEFAULT_TTL_HOURS.to_string()),
+            ConfigKey::Color => Some(color_name(ColorChoice::default())),
+            ConfigKey::Sources => Some(display_sources(&[Source::default()])),
+        }
+    }
+}
+
+impl fmt::Display for ConfigKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => bail!("Expected \"true\" or \"false\", got \"{}\".", value),
+    }
+}
+
+fn color_name(color: ColorChoice) -> String {
+    color.to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn display_sources(sources: &[Source]) -> String {
+    sources.iter()
+        .map(Source::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ProjectFygConfig {
-    pub version: String,
+    /// The version to use, or a range of versions like `~4.2`.
+    pub version: VersionReq,
     pub root: Option<PathBuf>,
+    /// Whether the project uses the Mono version of Godot with C# support.
+    #[serde(default)]
+    pub mono: bool,
+    /// Whether `fyg install` also installs export templates, e.g. for exporting in CI.
+    #[serde(default)]
+    pub export_templates: bool,
+    /// Settings that override the user's config while working in this project.
+    #[serde(flatten)]
+    pub settings: Settings,
 }
 
 impl ProjectFygConfig {
-    /// Load the project's fyg TOML config file at `project_fyg_dir`, which is usually the root of
-    /// the project's git directory.
-    pub fn load(project_fyg_dir: &Path) -> Result<ProjectFygConfig> {
-        for &config_name in PROJECT_FYG_CONFIGS {
-            let project_fyg_config_path = project_fyg_dir.join(config_name);
-            if !project_fyg_config_path.is_file() {
-                continue;
+    /// Find the project's fyg TOML config file in `project_fyg_dir`, if it has one.
+    pub fn find(project_fyg_dir: &Path) -> Option<PathBuf> {
+        PROJECT_FYG_CONFIGS.iter()
+            .map(|config_name| project_fyg_dir.join(config_name))
+            .find(|path| path.is_file())
+    }
+
+    /// Find the nearest project config in `start_dir` or any of its parents, like git does.
+    pub fn find_nearest(start_dir: &Path) -> Option<PathBuf> {
+        find_upwards(start_dir, Self::find)
+    }
+
+    /// Load a project's fyg TOML config file, which is usually at the root of the project's git
+    /// directory.
+    pub fn load_file(project_fyg_config_path: &Path) -> Result<ProjectFygConfig> {
+        let project_config_str = fs::read_to_string(project_fyg_config_path)
+            .with_context(|| format!("Could not read {}.", project_fyg_config_path.display()))?;
+        toml::from_str::<Self>(&project_config_str)
+            .with_context(|| format!("Could not parse {} as valid TOML.", project_fyg_config_path.display()))
+    }
+}
+
+/// Search `start_dir` and then each of its parents with `find`, stopping at the root of a git
+/// repository or the filesystem.
+pub fn find_upwards(start_dir: &Path, find: impl Fn(&Path) -> Option<PathBuf>) -> Option<PathBuf> {
+    for dir in start_dir.ancestors() {
+        if let Some(found) = find(dir) {
+            return Some(found);
+        }
+        // Don't leave the project's repository. `.git` is a file in worktrees and submodules.
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// The user's fyg config, stored in fyg's platform-specific config dir.
+pub struct UserFygConfig;
+
+impl UserFygConfig {
+    pub fn path() -> PathBuf {
+        FygDirs::get().config()
+            .join(USER_FYG_CONFIG)
+    }
+
+    /// Load the user's settings, or no settings if the user doesn't have a config.
+    pub fn load() -> Result<Settings> {
+        let user_config_path = Self::path();
+        if !user_config_path.is_file() {
+            return Ok(Settings::default());
+        }
+
+        let user_config_str = fs::read_to_string(&user_config_path)
+            .with_context(|| format!("Could not read {}.", user_config_path.display()))?;
+        toml::from_str::<Settings>(&user_config_str)
+            .with_context(|| format!("Could not parse {} as a valid config.", user_config_path.display()))
+    }
+
+    /// Set a value in the user's config, or remove it if `value` is `None`. Comments and
+    /// formatting in the rest of the file are kept.
+    pub fn set(key: ConfigKey, value: Option<&str>) -> Result<()> {
+        if key == ConfigKey::Sources {
+            // Sources span several tables, so leave them for the user to edit.
+            bail!("Sources are a list of tables. Edit {} to change them.", Self::path().display());
+        }
+
+        let user_config_path = Self::path();
+        let user_config_str = if user_config_path.is_file() {
+            fs::read_to_string(&user_config_path)
+                .with_context(|| format!("Could not read {}.", user_config_path.display()))?
+        } else {
+            String::new()
+        };
+        let mut document = user_config_str.parse::<toml_edit::DocumentMut>()
+            .with_context(|| format!("Could not parse {} as valid TOML.", user_config_path.display()))?;
+
+        match value {
+            Some(value) => {
+                // Parse the value first so only valid settings are written.
+                let mut settings = Settings::default();
+                key.parse_into(value, &mut settings)
+                    .with_context(|| format!("Invalid value for {}.", key))?;
+                let item = match key {
+                    ConfigKey::DefaultVersion => settings.default_version.map(|version| toml_edit::value(version.to_string())),
+                    ConfigKey::SelfContained => settings.self_contained.map(toml_edit::value),
+                    ConfigKey::CacheRetentionDays => settings.cache_retention_days.map(|days| toml_edit::value(i64::from(days))),
+                    ConfigKey::AutoInstall => settings.auto_install.map(toml_edit::value),
+                    ConfigKey::Offline => settings.offline.map(toml_edit::value),
+                    ConfigKey::ReleaseIndexTtlHours => settings.release_index_ttl_hours.map(|hours| toml_edit::value(i64::from(hours))),
+                    ConfigKey::GithubToken => settings.github_token.map(toml_edit::value),
+                    ConfigKey::Color => settings.color.map(|color| toml_edit::value(color_name(color))),
+                    ConfigKey::Sources => None,
+                };
+                if let Some(item) = item {
+                    document[key.name()] = item;
+                }
+            }
+            None => {
+                document.remove(key.name());
             }
-            let project_config_str = fs::read_to_string(&project_fyg_config_path)
-                .with_context(|| format!("Could not read {}.", project_fyg_config_path.display()))?;
-            return toml::from_str::<Self>(&project_config_str)
-                .with_context(|| format!("Could not parse {} as valid TOML.", project_fyg_config_path.display()));
         }
 
-        // TODO: Better error reporting. Collect errors from the for loop and report them here.
-        bail!("No valid config file ({}) found in {}.", PROJECT_FYG_CONFIGS.join(", "), project_fyg_dir.display());
+        fs::create_dir_all(FygDirs::get().config())
+            .with_context(|| format!("Could not create {}.", FygDirs::get().config().display()))?;
+        fs::write(&user_config_path, document.to_string())
+            .with_context(|| format!("Could not write {}.", user_config_path.display()))
+    }
+}
+
+/// Where a setting's value came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    CommandLine,
+    Env,
+    Project(PathBuf),
+    User(PathBuf),
+    Default,
+}
+
+impl Origin {
+    fn describe(&self, key: ConfigKey) -> String {
+        match self {
+            Origin::CommandLine => "command line".to_string(),
+            Origin::Env => key.env_value()
+                .map(|(env_var, _)| env_var)
+                .unwrap_or_default()
+                .to_string(),
+            Origin::Project(path) | Origin::User(path) => path.display().to_string(),
+            Origin::Default => "default".to_string(),
+        }
+    }
+}
+
+/// Settings merged from every layer. In order of precedence: command line flags, environment
+/// variables, the project's config, the user's config, and finally defaults.
+#[derive(Debug)]
+pub struct Config {
+    layers: Vec<(Origin, Settings)>,
+    /// Why layers that couldn't be loaded were left out.
+    errors: Vec<Error>,
+    /// Settings that were ignored while loading, to show once output is set up.
+    warnings: Vec<String>,
+}
+
+impl Config {
+    /// Load every layer of settings. `command_line` holds settings from flags, and `project_dir`
+    /// is where to start looking for a project config. Fails if any layer can't be loaded.
+    pub fn load(command_line: Settings, project_dir: &Path) -> Result<Config> {
+        Self::load_lenient(command_line, project_dir)
+            .into_checked()
+    }
+
+    /// Like `load`, but leave out layers that can't be loaded instead of failing, so fyg can still
+    /// show and fix its config when part of it is broken. See `errors` for what went wrong.
+    pub fn load_lenient(command_line: Settings, project_dir: &Path) -> Config {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let mut layers = vec![(Origin::CommandLine, command_line)];
+        match Self::load_env() {
+            Ok(env) => layers.push((Origin::Env, env)),
+            Err(e) => errors.push(e),
+        }
+        if let Some(project_config_path) = ProjectFygConfig::find_nearest(project_dir) {
+            match ProjectFygConfig::load_file(&project_config_path) {
+                Ok(mut project_config) => {
+                    warnings.extend(project_config.settings.remove_untrusted(&project_config_path));
+                    layers.push((Origin::Project(project_config_path), project_config.settings));
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        match UserFygConfig::load() {
+            Ok(user) => layers.push((Origin::User(UserFygConfig::path()), user)),
+            Err(e) => errors.push(e),
+        }
+
+        Config { layers, errors, warnings }
+    }
+
+    /// Fail with the first error from loading, if any layer couldn't be loaded.
+    pub fn into_checked(mut self) -> Result<Config> {
+        if self.errors.is_empty() {
+            return Ok(self);
+        }
+        Err(self.errors.remove(0))
+    }
+
+    /// Why layers were left out when loading leniently.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// Warnings about settings that were ignored while loading.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    fn load_env() -> Result<Settings> {
+        let mut settings = Settings::default();
+        for &key in ConfigKey::ALL {
+            let Some((env_var, value)) = key.env_value() else {
+                continue;
+            };
+            key.parse_into(&value, &mut settings)
+                .with_context(|| format!("Invalid value for {}.", env_var))?;
+        }
+        Ok(settings)
+    }
+
+    /// Find the first layer that sets a value.
+    fn get<T>(&self, field: impl Fn(&Settings) -> Option<T>) -> Option<T> {
+        self.layers.iter()
+            .find_map(|(_, settings)| field(settings))
+    }
+
+    pub fn default_version(&self) -> Option<GodotVersion> {
+        self.get(|settings| settings.default_version.clone())
+    }
+
+    pub fn self_contained(&self) -> bool {
+        self.get(|settings| settings.self_contained)
+            .unwrap_or(true)
+    }
+
+    pub fn cache_retention_days(&self) -> Option<u32> {
+        self.get(|settings| settings.cache_retention_days)
+    }
+
+    /// Whether `edit` and `run` should install a missing engine. `None` means ask.
+    pub fn auto_install(&self) -> Option<bool> {
+        self.get(|settings| settings.auto_install)
+    }
+
+    pub fn offline(&self) -> bool {
+        self.get(|settings| settings.offline)
+            .unwrap_or(false)
+    }
+
+    pub fn release_index_ttl_hours(&self) -> u32 {
+        self.get(|settings| settings.release_index_ttl_hours)
+            .unwrap_or(release_index::DEFAULT_TTL_HOURS)
+    }
+
+    pub fn github_token(&self) -> Option<String> {
+        self.get(|settings| settings.github_token.clone())
+    }
+
+    pub fn color(&self) -> ColorChoice {
+        self.get(|settings| settings.color)
+            .unwrap_or_default()
+    }
+
+    /// The configured sources, or GitHub if none are configured.
+    pub fn sources(&self) -> Vec<Source> {
+        self.get(|settings| settings.sources.clone().filter(|sources| !sources.is_empty()))
+            .unwrap_or_else(|| vec![Source::default()])
+    }
+
+    /// Find a setting's effective value for display, along with a description of where it came
+    /// from. Returns `None` if the setting isn't set and has no default.
+    pub fn lookup(&self, key: ConfigKey) -> Option<(String, String)> {
+        let found = self.layers.iter()
+            .find_map(|(origin, settings)| key.display(settings).map(|value| (value, origin)));
+        match found {
+            Some((value, origin)) => Some((value, origin.describe(key))),
+            None => key.display_default()
+                .map(|value| (value, Origin::Default.describe(key))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_config_cant_redirect_github_token() {
+        let mut project_config = toml::from_str::<ProjectFygConfig>(r#"
+            version = "4.2"
+            github_token = "secret"
+
+            [[sources]]
+            type = "github"
+            api_url = "https://attacker.example"
+
+            [[sources]]
+            type = "github"
+
+            [[sources]]
+            type = "mirror"
+            url = "https://mirror.example/godot"
+        "#).unwrap();
+
+        let warnings = project_config.settings.remove_untrusted(Path::new("fyg.toml"));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[1].contains("https://attacker.example"));
+        assert_eq!(project_config.settings.github_token, None);
+        let sources = project_config.settings.sources.unwrap();
+        assert_eq!(display_sources(&sources), "GitHub godotengine/godot (https://api.github.com), mirror https://mirror.example/godot");
+        assert!(!sources.iter().any(Source::is_custom_github));
     }
 }
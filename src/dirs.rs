@@ -6,51 +6,80 @@ use directories::BaseDirs;
 const FYG_DIR: &str = "find-your-godot";
 
 pub struct FygDirs {
+    config_dir: PathBuf,
+    bin_dir: PathBuf,
     engines_data_dir: PathBuf,
+    cache_dir: PathBuf,
     engines_cache_dir: PathBuf,
 }
 
 impl FygDirs {
     pub fn get() -> &'static Self {
         static DIRS: OnceLock<FygDirs> = OnceLock::new();
-        DIRS.get_or_init(|| Self::new())
+        DIRS.get_or_init(Self::new)
     }
 
     pub fn new() -> Self {
         let Some(base_dirs) = BaseDirs::new() else {
             return Self {
+                config_dir: PathBuf::new(),
+                bin_dir: PathBuf::new(),
                 engines_data_dir: PathBuf::new(),
+                cache_dir: PathBuf::new(),
                 engines_cache_dir: PathBuf::new(),
             }
         };
 
-        let mut engines_cache_dir = base_dirs.cache_dir()
+        let mut cache_dir = base_dirs.cache_dir()
             .join(FYG_DIR);
         // Add an intermediate cache directory on Windows since it's placed in ~/AppData/Local
         // with other things by default.
         if cfg!(target_os = "windows") {
-            engines_cache_dir.push("cache");
+            cache_dir.push("cache");
         }
-        engines_cache_dir.push("engines");
+        let engines_cache_dir = cache_dir.join("engines");
 
         Self {
+            config_dir: base_dirs.config_dir()
+                .join(FYG_DIR),
+            bin_dir: base_dirs.data_dir()
+                .join(FYG_DIR)
+                .join("bin"),
             engines_data_dir: base_dirs.data_dir()
                 .join(FYG_DIR)
                 .join("engines"),
+            cache_dir,
             engines_cache_dir,
         }
     }
 
+    pub fn config(&self) -> &Path {
+        &self.config_dir
+    }
+
+    /// Where the `godot` shims live. Users add this to their PATH.
+    pub fn bin(&self) -> &Path {
+        &self.bin_dir
+    }
+
     pub fn engines_data(&self) -> &Path {
         &self.engines_data_dir
     }
 
+    /// Where fyg caches things it can download again, like the release index.
+    pub fn cache(&self) -> &Path {
+        &self.cache_dir
+    }
+
     pub fn engines_cache(&self) -> &Path {
         &self.engines_cache_dir
     }
 
     pub fn is_valid(&self) -> bool {
-        !self.engines_cache_dir.as_os_str().is_empty() &&
+        !self.config_dir.as_os_str().is_empty() &&
+            !self.bin_dir.as_os_str().is_empty() &&
+            !self.cache_dir.as_os_str().is_empty() &&
+            !self.engines_cache_dir.as_os_str().is_empty() &&
             !self.engines_data_dir.as_os_str().is_empty()
     }
 }
@@ -0,0 +1,377 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use reqwest::{header, Client, StatusCode};
+
+use crate::{
+    checksum,
+    output,
+    progress::ProgressBar,
+};
+
+pub const DEFAULT_ATTEMPTS: u32 = 5;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many times to try a download and how long to wait between attempts. The wait doubles
+/// after each failed attempt, up to `max_backoff`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn with_attempts(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            ..Default::default()
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: DEFAULT_ATTEMPTS,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Why a single download attempt failed.
+enum AttemptError {
+    /// Worth retrying, e.g. a dropped connection or a server error.
+    Transient(Error),
+    /// Retrying won't help, e.g. a 404 or failing to write to disk.
+    Fatal(Error),
+}
+
+impl From<reqwest::Error> for AttemptError {
+    fn from(error: reqwest::Error) -> Self {
+        let transient = error.is_connect() ||
+            error.is_timeout() ||
+            error.is_body() ||
+            error.is_request() ||
+            error.is_decode() ||
+            error.status().is_some_and(is_transient_status);
+        if transient {
+            Self::Transient(error.into())
+        } else {
+            Self::Fatal(error.into())
+        }
+    }
+}
+
+impl From<std::io::Error> for AttemptError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Fatal(error.into())
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() ||
+        status == StatusCode::REQUEST_TIMEOUT ||
+        status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Path of the partially downloaded file for `dest`.
+pub fn part_path(dest: &Path) -> PathBuf {
+    let mut part_path = dest.as_os_str().to_owned();
+    part_path.push(".part");
+    PathBuf::from(part_path)
+}
+
+pub fn client() -> Result<Client> {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+        .context("Could not create HTTP client.")
+}
+
+/// Stream the file at `url` into a `.part` file next to `dest`, showing a progress bar, then
+/// rename it to `dest`.
+///
+/// If a `.part` file is left over from an interrupted download, it's resumed with an HTTP Range
+/// request. Transient failures are retried according to `retry`, resuming from wherever the
+/// previous attempt stopped.
+///
+/// If `expected_sum` is given, the download is checked against it before being renamed, so `dest`
+/// never holds a corrupted file. Returns the SHA-512 sum of the file.
+pub async fn download_file(
+    client: &Client,
+    url: &str,
+    dest: &Path,
+    expected_sum: Option<&str>,
+    retry: &RetryPolicy,
+) -> Result<String> {
+    let part_path = part_path(dest);
+
+    let mut attempt = 1;
+    loop {
+        match try_download(client, url, &part_path).await {
+            Ok(()) => break,
+            Err(AttemptError::Transient(error)) if attempt < retry.attempts => {
+                let backoff = retry.backoff(attempt);
+                let warning = format!(
+                    "Download interrupted: {}. Retrying in {}s (attempt {}/{}).",
+                    error,
+                    backoff.as_secs(),
+                    attempt + 1,
+                    retry.attempts,
+                );
+                output::warn(&warning);
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(AttemptError::Transient(error) | AttemptError::Fatal(error)) => {
+                return Err(error.context(format!(
+                    "Could not download {} after {} attempt(s). Run the command again to resume the download.",
+                    url,
+                    attempt,
+                )));
+            }
+        }
+    }
+
+    let actual_sum = checksum::sha512_file(&part_path)?;
+    if let Some(expected_sum) = expected_sum {
+        if expected_sum != actual_sum {
+            fs::remove_file(&part_path)?;
+            bail!(
+                "Downloaded {} does not match its SHA-512 checksum.\nExpected: {}\nActual: {}\nThe download may be corrupted. Try again.",
+                url,
+                expected_sum,
+                actual_sum,
+            );
+        }
+    }
+
+    fs::rename(&part_path, dest)
+        .with_context(|| format!("Could not move {} to {}.", part_path.display(), dest.display()))?;
+
+    Ok(actual_sum)
+}
+
+/// The first byte of a partial response, from its `Content-Range` header, e.g. `bytes 100-999/1000`.
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    response.headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Download the rest of the file into `part_path`, resuming from what's already there.
+async fn try_download(client: &Client, url: &str, part_path: &Path) -> Result<(), AttemptError> {
+    let offset = part_path.metadata()
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await?;
+
+    let (mut file, offset) = match response.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            // Appending anything but what comes right after the partial file would corrupt it.
+            let range_start = content_range_start(&response);
+            if range_start != Some(offset) {
+                fs::remove_file(part_path)?;
+                return Err(AttemptError::Transient(anyhow!(
+                    "Server resumed at byte {} instead of {}, so starting over",
+                    range_start.map_or_else(|| "unknown".to_string(), |start| start.to_string()),
+                    offset,
+                )));
+            }
+            let file = fs::OpenOptions::new()
+                .append(true)
+                .open(part_path)?;
+            (file, offset)
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // The partial file is no good, so start over on the next attempt.
+            fs::remove_file(part_path)?;
+            return Err(AttemptError::Transient(anyhow!("Could not resume partial download")));
+        }
+        _ => {
+            // The server sent the whole file, either because we asked for it or because it
+            // doesn't support ranges.
+            response = response.error_for_status()?;
+            (fs::File::create(part_path)?, 0)
+        }
+    };
+
+    let total = response.content_length()
+        .map(|remaining| offset + remaining);
+    let mut progress = ProgressBar::new(total, offset);
+    let result = loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                file.write_all(&chunk)?;
+                progress.inc(chunk.len() as u64);
+            }
+            Ok(None) => break Ok(()),
+            Err(error) => break Err(error),
+        }
+    };
+    // Finish the bar either way so retry messages start on their own line.
+    progress.finish();
+    result?;
+    file.flush()?;
+
+    // Catch connections that closed cleanly before sending everything.
+    if let Some(total) = total {
+        let downloaded = file.metadata()?.len();
+        if downloaded < total {
+            return Err(AttemptError::Transient(anyhow!(
+                "Connection closed after {} of {} bytes",
+                downloaded,
+                total,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::test_util::{self, Response, TempDir};
+
+    /// How the test server answers a request.
+    #[derive(Clone, Copy)]
+    enum Reply {
+        /// Honor the Range header, but close the connection after sending this many bytes.
+        CutOff(usize),
+        /// Honor the Range header and send the rest of the file.
+        Full,
+        /// Send a partial response starting at this byte, whatever was asked for.
+        WrongRange(usize),
+    }
+
+    /// Serve `body` on a local port, answering the nth request with `replies[n]`, or the last reply
+    /// once they run out. Returns the file's URL and where each request asked to start from.
+    async fn serve(body: Vec<u8>, replies: Vec<Reply>) -> (String, Arc<Mutex<Vec<usize>>>) {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let server_requested = requested.clone();
+        let url = test_util::serve(move |request| {
+            let start = request.header("range")
+                .and_then(|range| range.strip_prefix("bytes="))
+                .map_or(0, |range| range.trim_end_matches('-').parse().unwrap());
+            let mut requested = server_requested.lock().unwrap();
+            let reply = replies[requested.len().min(replies.len() - 1)];
+            requested.push(start);
+
+            let (status, start) = match reply {
+                Reply::WrongRange(wrong_start) => ("206 Partial Content", wrong_start),
+                _ if start > 0 => ("206 Partial Content", start),
+                _ => ("200 OK", 0),
+            };
+            let mut response = Response::new(status, &body[start..]);
+            if status.starts_with("206") {
+                response = response.header("Content-Range", format!("bytes {}-{}/{}", start, body.len() - 1, body.len()));
+            }
+            if let Reply::CutOff(bytes) = reply {
+                response = response.cut_off(bytes);
+            }
+            response
+        }).await;
+        (format!("{}/Godot.zip", url), requested)
+    }
+
+    fn body() -> Vec<u8> {
+        (0..1000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn no_backoff(attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            attempts,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn resumes_dropped_downloads() {
+        let (url, requested) = serve(body(), vec![Reply::CutOff(300), Reply::CutOff(300), Reply::Full]).await;
+        let dir = TempDir::new();
+        let dest = dir.path().join("Godot.zip");
+
+        let sum = download_file(&client().unwrap(), &url, &dest, None, &no_backoff(5)).await.unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), body());
+        assert_eq!(sum, checksum::sha512_file(&dest).unwrap());
+        assert!(!part_path(&dest).exists());
+        assert_eq!(*requested.lock().unwrap(), [0, 300, 600]);
+    }
+
+    #[tokio::test]
+    async fn stops_after_configured_attempts() {
+        let (url, requested) = serve(body(), vec![Reply::CutOff(100)]).await;
+        let dir = TempDir::new();
+        let dest = dir.path().join("Godot.zip");
+
+        let error = download_file(&client().unwrap(), &url, &dest, None, &no_backoff(3)).await.unwrap_err();
+
+        assert_eq!(requested.lock().unwrap().len(), 3);
+        let message = format!("{:#}", error);
+        assert!(message.contains(&format!("Could not download {} after 3 attempt(s).", url)), "{}", message);
+        assert!(message.contains("Run the command again to resume the download."), "{}", message);
+        // What was downloaded is kept to resume from next time.
+        assert_eq!(fs::read(part_path(&dest)).unwrap(), body()[..300]);
+        assert!(!dest.exists());
+    }
+
+    #[tokio::test]
+    async fn restarts_when_resumed_at_wrong_offset() {
+        let (url, requested) = serve(body(), vec![Reply::WrongRange(0), Reply::Full]).await;
+        let dir = TempDir::new();
+        let dest = dir.path().join("Godot.zip");
+        fs::write(part_path(&dest), &body()[..100]).unwrap();
+
+        download_file(&client().unwrap(), &url, &dest, None, &no_backoff(2)).await.unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), body());
+        assert_eq!(*requested.lock().unwrap(), [100, 0]);
+    }
+
+    #[tokio::test]
+    async fn rejects_checksum_mismatch() {
+        let (url, _) = serve(body(), vec![Reply::Full]).await;
+        let dir = TempDir::new();
+        let dest = dir.path().join("Godot.zip");
+
+        let error = download_file(&client().unwrap(), &url, &dest, Some("bad"), &no_backoff(1)).await.unwrap_err();
+
+        assert!(error.to_string().contains("does not match its SHA-512 checksum"));
+        assert!(!dest.exists());
+        assert!(!part_path(&dest).exists());
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    assets::AssetNames,
+    platform::Platform,
+    version::GodotVersion,
+};
+
+/// Name of the file that turns on Self-Contained Mode:
+/// https://docs.godotengine.org/en/latest/tutorials/io/data_paths.html#self-contained-mode
+const SELF_CONTAINED_FILE: &str = "_sc_";
+
+/// Directory next to the `_sc_` file where a self-contained editor keeps its settings and data.
+const EDITOR_DATA_DIR: &str = "editor_data";
+
+/// File in the install dir recording the SHA-512 sum of the zip the engine was extracted from.
+const PACKAGE_SUM_FILE: &str = "fyg-package.sha512";
+
+/// Path of the executable inside a macOS app bundle.
+const MAC_BUNDLE_EXECUTABLE: &str = "Contents/MacOS/Godot";
+
+/// Where an engine version is installed and how to run it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EngineInstall {
+    /// Directory the engine's zip is extracted to.
+    pub dir: PathBuf,
+    /// The executable to run. On macOS, this is inside the app bundle.
+    pub executable: PathBuf,
+    /// Directory the `_sc_` file goes in to make Godot use Self-Contained Mode.
+    pub self_contained_dir: PathBuf,
+}
+
+impl EngineInstall {
+    /// Resolve the paths of an engine extracted to `dir` from the zip described by `asset_names`.
+    pub fn new(dir: PathBuf, version: &GodotVersion, platform: Platform, asset_names: &AssetNames) -> Self {
+        let binary = dir.join(&asset_names.binary);
+        if platform != Platform::MacOS {
+            // The _sc_ file goes next to the executable, which for Mono builds is in a
+            // directory alongside GodotSharp.
+            let self_contained_dir = binary.parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| dir.clone());
+            return Self {
+                dir,
+                executable: binary,
+                self_contained_dir,
+            };
+        }
+
+        // On macOS the zip holds an app bundle. Godot 3 looks for _sc_ next to the executable
+        // inside the bundle, while Godot 4 looks next to the bundle since bundles may be
+        // read-only.
+        let executable = binary.join(MAC_BUNDLE_EXECUTABLE);
+        let self_contained_dir = if version.major >= 4 {
+            binary.parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| dir.clone())
+        } else {
+            executable.parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| dir.clone())
+        };
+        Self {
+            dir,
+            executable,
+            self_contained_dir,
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.executable.is_file()
+    }
+
+    /// Extract an engine zip into the install dir, checking that it held the executable where
+    /// we expect it.
+    pub fn extract(&self, zip_path: &Path) -> Result<()> {
+        let zip_file = fs::File::open(zip_path)
+            .with_context(|| format!("Could not open {}.", zip_path.display()))?;
+        let mut archive = zip::ZipArchive::new(zip_file)
+            .with_context(|| format!("Could not read {} as a zip.", zip_path.display()))?;
+        archive.extract(&self.dir)
+            .with_context(|| format!("Could not extract {}.", zip_path.display()))?;
+
+        if !self.is_installed() {
+            bail!(
+                "Extracted {}, but could not find the engine at {}.",
+                zip_path.display(),
+                self.executable.display(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// By default, add an _sc_ file so Godot keeps its settings and data alongside the engine
+    /// instead of in the user's home directory.
+    pub fn create_self_contained_file(&self) -> Result<()> {
+        let path = self.self_contained_dir.join(SELF_CONTAINED_FILE);
+        fs::File::create(&path)
+            .with_context(|| format!("Could not create {}.", path.display()))?;
+
+        Ok(())
+    }
+
+    pub fn is_self_contained(&self) -> bool {
+        self.self_contained_dir.join(SELF_CONTAINED_FILE).is_file()
+    }
+
+    /// Where the editor keeps its settings and data in Self-Contained Mode.
+    pub fn editor_data_dir(&self) -> PathBuf {
+        self.self_contained_dir.join(EDITOR_DATA_DIR)
+    }
+
+    /// Record the SHA-512 sum of the zip the engine was extracted from, so the install can be
+    /// checked against a lockfile later.
+    pub fn write_package_sum(&self, sum: &str) -> Result<()> {
+        let path = self.dir.join(PACKAGE_SUM_FILE);
+        fs::write(&path, format!("{}\n", sum))
+            .with_context(|| format!("Could not write {}.", path.display()))
+    }
+
+    /// The SHA-512 sum of the zip the engine was extracted from. Returns `None` for engines
+    /// installed before fyg recorded it.
+    pub fn read_package_sum(&self) -> Result<Option<String>> {
+        let path = self.dir.join(PACKAGE_SUM_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let sum = fs::read_to_string(&path)
+            .with_context(|| format!("Could not read {}.", path.display()))?;
+        Ok(Some(sum.trim().to_ascii_lowercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+    use crate::{assets, test_util::TempDir};
+
+    /// Write a zip holding `files` to `path`.
+    fn write_zip(path: &Path, files: &[&str]) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        for file in files {
+            zip.start_file(*file, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"fake godot").unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Extract a fixture zip holding `files` into an install dir for `tag` on `platform`.
+    fn install(dir: &TempDir, tag: &str, platform: Platform, mono: bool, files: &[&str]) -> EngineInstall {
+        let version: GodotVersion = tag.parse().unwrap();
+        let asset_names = assets::resolve(&version, platform, mono).unwrap();
+        let zip_path = dir.path().join(&asset_names.zip);
+        write_zip(&zip_path, files);
+
+        let engine = EngineInstall::new(dir.path().join("engine"), &version, platform, &asset_names);
+        assert!(!engine.is_installed());
+        engine.extract(&zip_path).unwrap();
+        assert!(engine.is_installed());
+        engine
+    }
+
+    #[test]
+    fn installs_flat_linux_binary() {
+        let dir = TempDir::new();
+        let engine = install(&dir, "4.2.1-stable", Platform::Linux64, false, &["Godot_v4.2.1-stable_linux.x86_64"]);
+
+        let engine_dir = dir.path().join("engine");
+        assert_eq!(engine.executable, engine_dir.join("Godot_v4.2.1-stable_linux.x86_64"));
+        assert_eq!(engine.self_contained_dir, engine_dir);
+    }
+
+    #[test]
+    fn installs_nested_mono_binary() {
+        let dir = TempDir::new();
+        let engine = install(&dir, "4.2.1-stable", Platform::Linux64, true, &[
+            "Godot_v4.2.1-stable_mono_linux_x86_64/Godot_v4.2.1-stable_mono_linux.x86_64",
+            "Godot_v4.2.1-stable_mono_linux_x86_64/GodotSharp/Api/Release/GodotSharp.dll",
+        ]);
+
+        // The _sc_ file goes next to the executable, alongside GodotSharp.
+        let mono_dir = dir.path().join("engine/Godot_v4.2.1-stable_mono_linux_x86_64");
+        assert_eq!(engine.executable, mono_dir.join("Godot_v4.2.1-stable_mono_linux.x86_64"));
+        assert_eq!(engine.self_contained_dir, mono_dir);
+
+        engine.create_self_contained_file().unwrap();
+        assert!(engine.is_self_contained());
+        assert!(mono_dir.join(SELF_CONTAINED_FILE).is_file());
+        assert_eq!(engine.editor_data_dir(), mono_dir.join(EDITOR_DATA_DIR));
+    }
+
+    #[test]
+    fn installs_macos_bundle() {
+        let dir = TempDir::new();
+        let engine = install(&dir, "4.2.1-stable", Platform::MacOS, false, &["Godot.app/Contents/MacOS/Godot", "Godot.app/Contents/Info.plist"]);
+
+        // Godot 4 looks for _sc_ next to the bundle, since bundles may be read-only.
+        let engine_dir = dir.path().join("engine");
+        assert_eq!(engine.executable, engine_dir.join("Godot.app").join(MAC_BUNDLE_EXECUTABLE));
+        assert_eq!(engine.self_contained_dir, engine_dir);
+    }
+
+    #[test]
+    fn installs_godot_3_macos_bundle() {
+        let dir = TempDir::new();
+        let engine = install(&dir, "3.5.3-stable", Platform::MacOS, false, &["Godot.app/Contents/MacOS/Godot"]);
+
+        // Godot 3 looks for _sc_ next to the executable, inside the bundle.
+        let macos_dir = dir.path().join("engine/Godot.app/Contents/MacOS");
+        assert_eq!(engine.executable, macos_dir.join("Godot"));
+        assert_eq!(engine.self_contained_dir, macos_dir);
+
+        engine.create_self_contained_file().unwrap();
+        assert!(macos_dir.join(SELF_CONTAINED_FILE).is_file());
+    }
+
+    #[test]
+    fn rejects_zip_without_executable() {
+        let dir = TempDir::new();
+        let version: GodotVersion = "4.2.1-stable".parse().unwrap();
+        let asset_names = assets::resolve(&version, Platform::Linux64, false).unwrap();
+        let zip_path = dir.path().join(&asset_names.zip);
+        write_zip(&zip_path, &["Godot_v4.2.1-stable_linux.arm64"]);
+
+        let engine = EngineInstall::new(dir.path().join("engine"), &version, Platform::Linux64, &asset_names);
+        let error = engine.extract(&zip_path).unwrap_err();
+        assert!(error.to_string().contains("could not find the engine"), "{}", error);
+        assert!(!engine.is_installed());
+    }
+}
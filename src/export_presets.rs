@@ -0,0 +1,65 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+pub static EXPORT_PRESETS_NAME: &str = "export_presets.cfg";
+
+/// An export preset from a project's export_presets.cfg.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportPreset {
+    pub name: String,
+    /// The platform the preset exports for, e.g. `Linux/X11` or `Web`.
+    pub platform: String,
+    /// Where the preset exports to, relative to the project. Empty if it was never set.
+    pub export_path: String,
+}
+
+/// Load the presets in an export_presets.cfg, in the order they're listed in the editor.
+pub fn load(path: &Path) -> Result<Vec<ExportPreset>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}.", path.display()))?;
+    Ok(parse(&contents))
+}
+
+/// Parse the presets in an export_presets.cfg. It's an INI-like format like project.godot, with a
+/// `[preset.N]` section for each preset followed by a `[preset.N.options]` section.
+pub fn parse(contents: &str) -> Vec<ExportPreset> {
+    let mut presets = Vec::new();
+    let mut in_preset = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            in_preset = name.strip_prefix("preset.")
+                .is_some_and(|index| index.parse::<u32>().is_ok());
+            if in_preset {
+                presets.push(ExportPreset::default());
+            }
+            continue;
+        }
+        let (true, Some(preset), Some((key, value))) = (in_preset, presets.last_mut(), line.split_once('=')) else {
+            continue;
+        };
+
+        let value = parse_string(value);
+        match key.trim() {
+            "name" => preset.name = value,
+            "platform" => preset.platform = value,
+            "export_path" => preset.export_path = value,
+            _ => {}
+        }
+    }
+    presets
+}
+
+/// Parse a quoted string value like `"Linux/X11"`, unescaping quotes and backslashes.
+fn parse_string(value: &str) -> String {
+    let value = value.trim();
+    let value = value.strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    value.replace("\\\"", "\"")
+        .replace("\\\\", "\\")
+}
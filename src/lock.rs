@@ -0,0 +1,73 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    platform::Platform,
+    version::GodotVersion,
+};
+
+pub static LOCKFILE_NAME: &str = "fyg.lock";
+
+const LOCKFILE_HEADER: &str = "\
+# This file is generated by `fyg lock`. Commit it so everyone on the project uses the same engine
+# build. Run `fyg lock` again to update it.
+";
+
+/// Pins a project to an exact engine build, recorded next to its fyg config.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Lockfile {
+    /// The full version, e.g. `4.2.1-stable`.
+    pub version: GodotVersion,
+    /// Whether the project uses the Mono build with C# support.
+    pub mono: bool,
+    /// The release's zip for each platform Godot publishes one for.
+    #[serde(default)]
+    pub assets: Vec<LockedAsset>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LockedAsset {
+    /// The platform's name as passed to `--platform`, e.g. `linux-64`.
+    pub platform: String,
+    pub name: String,
+    /// The zip's SHA-512 sum. Missing for releases that don't publish sums.
+    pub sha512: Option<String>,
+}
+
+impl Lockfile {
+    /// Path of the lockfile for the project config at `config_path`.
+    pub fn path(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(LOCKFILE_NAME)
+    }
+
+    /// Load the lockfile at `path`, if the project has one.
+    pub fn load(path: &Path) -> Result<Option<Lockfile>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let lock_str = fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}.", path.display()))?;
+        toml::from_str(&lock_str)
+            .with_context(|| format!("Could not parse {} as a valid lockfile.", path.display()))
+            .map(Some)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let lock_str = toml::to_string_pretty(self)
+            .context("Could not serialize lockfile.")?;
+        fs::write(path, format!("{}\n{}", LOCKFILE_HEADER, lock_str))
+            .with_context(|| format!("Could not write {}.", path.display()))
+    }
+
+    /// The locked zip for a platform, if the release has one.
+    pub fn asset(&self, platform: Platform) -> Option<&LockedAsset> {
+        let platform_name = platform.name();
+        self.assets.iter()
+            .find(|asset| asset.platform == platform_name)
+    }
+}
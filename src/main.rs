@@ -1,24 +1,48 @@
 use anyhow::{bail, Result};
 
+mod assets;
+mod checksum;
 mod cli;
 mod commands;
 mod config;
 mod dirs;
+mod download;
+mod engine;
+mod export_presets;
+mod lock;
+mod output;
 mod platform;
+mod progress;
+mod project;
+mod project_godot;
+mod release_index;
+mod sources;
+mod templates;
+#[cfg(test)]
+mod test_util;
 mod version;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     use clap::Parser;
 
-    let cli = cli::Cli::parse();
-
     // Initialize dirs and verify that it succeeded.
     if !dirs::FygDirs::get().is_valid() {
         bail!("Could not initialize app directories.");
     }
 
-    commands::run_command(&cli.command).await?;
+    // When run through a `godot` shim, pass every argument through to the engine.
+    if commands::run_shim()? {
+        return Ok(());
+    }
+
+    let cli = cli::Cli::parse();
+
+    if let Some(platform) = cli.platform {
+        platform::Platform::set_override(platform);
+    }
+
+    commands::run_command(&cli).await?;
 
     Ok(())
 }
@@ -0,0 +1,103 @@
+use std::{
+    env,
+    io::{self, BufRead, IsTerminal, Write},
+    sync::OnceLock,
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+
+/// When to color output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Color output when writing to a terminal, unless NO_COLOR is set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// How query commands like `list` print their results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Text for people to read, which may change between releases.
+    #[default]
+    Text,
+    /// JSON with a stable schema, for scripts and tools.
+    Json,
+}
+
+static COLOR: OnceLock<bool> = OnceLock::new();
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+/// Decide whether to color output and how to print results for the rest of the run. Must be
+/// called before any output.
+pub fn init(color: ColorChoice, format: Format) {
+    let _ = FORMAT.set(format);
+    let enabled = match color {
+        ColorChoice::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    let _ = COLOR.set(enabled);
+}
+
+fn color_enabled() -> bool {
+    *COLOR.get_or_init(|| io::stdout().is_terminal())
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get()
+        .is_some_and(|&format| format == Format::Json)
+}
+
+/// Print a command's result as JSON.
+pub fn print_json(value: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)
+        .context("Could not serialize output as JSON.")?;
+    println!("{}", json);
+    Ok(())
+}
+
+pub fn bold(text: &str) -> String {
+    if color_enabled() {
+        text.bold().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn dimmed(text: &str) -> String {
+    if color_enabled() {
+        text.dimmed().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Print a warning to stderr, so it stays out of output meant for other programs, like JSON.
+pub fn warn(text: &str) {
+    if color_enabled() {
+        eprintln!("{}", text.yellow());
+    } else {
+        eprintln!("{}", text);
+    }
+}
+
+/// Ask the user a yes or no question, defaulting to no. Returns `false` without asking when fyg
+/// isn't running in a terminal.
+pub fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Ok(false);
+    }
+
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock()
+        .read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
@@ -1,45 +1,178 @@
-// Compile time detection of platform we're running on.
-pub const PLATFORM: Platform = if cfg!(target_os = "windows") {
-    if cfg!(target_arch = "x86") {
-        Platform::Windows32
-    } else if cfg!(target_arch = "x86_64") {
-        Platform::Windows64
-    } else {
-        Platform::Unsupported
-    }
-} else if cfg!(target_os = "macos") {
-    Platform::MacOS
-} else if cfg!(target_os = "linux") {
-    if cfg!(target_arch = "x86") {
-        Platform::Linux32
-    } else if cfg!(target_arch = "x86_64") {
-        Platform::Linux64
-    } else {
-        Platform::Unsupported
-    }
-} else {
-    Platform::Unsupported
+use std::{
+    env, fmt,
+    process::Command,
+    sync::OnceLock,
 };
 
-#[derive(Clone, Copy, Debug)]
+use clap::ValueEnum;
+
+use crate::assets;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 pub enum Platform {
+    #[value(name = "windows-32")]
     Windows32,
+    #[value(name = "windows-64")]
     Windows64,
+    #[value(name = "windows-arm64")]
+    WindowsArm64,
+    #[value(name = "macos")]
     MacOS,
+    #[value(name = "linux-32")]
     Linux32,
+    #[value(name = "linux-64")]
     Linux64,
+    #[value(name = "linux-arm32")]
+    LinuxArm32,
+    #[value(name = "linux-arm64")]
+    LinuxArm64,
+    /// Linux build without rendering or audio, for running games and tools in CI. Godot 3 only.
+    #[value(name = "linux-headless")]
+    LinuxHeadless,
+    /// Linux build for running dedicated game servers. Godot 2 and 3 only.
+    #[value(name = "linux-server")]
+    LinuxServer,
+    #[value(skip)]
     Unsupported,
 }
 
+static HOST_PLATFORM: OnceLock<Platform> = OnceLock::new();
+static PLATFORM: OnceLock<Platform> = OnceLock::new();
+
 impl Platform {
-    pub fn to_package(&self) -> &'static str {
-        match self {
-            Platform::Windows32 => "win32.exe",
-            Platform::Windows64 => "win64.exe",
-            Platform::MacOS => "osx.universal",
-            Platform::Linux32 => "x11.32",
-            Platform::Linux64 => "x11.64",
-            Platform::Unsupported => "unsupported",
+    /// Every platform Godot publishes builds for.
+    pub const ALL: &'static [Platform] = &[
+        Platform::Windows32,
+        Platform::Windows64,
+        Platform::WindowsArm64,
+        Platform::MacOS,
+        Platform::Linux32,
+        Platform::Linux64,
+        Platform::LinuxArm32,
+        Platform::LinuxArm64,
+        Platform::LinuxHeadless,
+        Platform::LinuxServer,
+    ];
+
+    /// The platform to get engine builds for. This is the platform we're running on, unless it
+    /// was overridden with `--platform`.
+    pub fn get() -> Self {
+        *PLATFORM.get_or_init(Self::host)
+    }
+
+    /// Use builds for another platform instead of the one we're running on. Must be called
+    /// before the first call to `get`.
+    pub fn set_override(platform: Self) {
+        let _ = PLATFORM.set(platform);
+    }
+
+    /// The platform we're running on, detected at runtime so that e.g. a 32-bit build of fyg on
+    /// 64-bit Windows still gets 64-bit engines.
+    pub fn host() -> Self {
+        *HOST_PLATFORM.get_or_init(Self::detect)
+    }
+
+    fn detect() -> Self {
+        match env::consts::OS {
+            "windows" => {
+                // PROCESSOR_ARCHITEW6432 is only set when running under WOW64 emulation, and
+                // holds the real architecture.
+                let arch = env::var("PROCESSOR_ARCHITEW6432")
+                    .or_else(|_| env::var("PROCESSOR_ARCHITECTURE"))
+                    .unwrap_or_else(|_| env::consts::ARCH.to_string());
+                match arch.to_ascii_lowercase().as_str() {
+                    "amd64" | "x86_64" => Platform::Windows64,
+                    "arm64" | "aarch64" => Platform::WindowsArm64,
+                    "x86" => Platform::Windows32,
+                    _ => Platform::Unsupported,
+                }
+            }
+            "macos" => Platform::MacOS,
+            "linux" => {
+                let arch = Command::new("uname")
+                    .arg("-m")
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                    .unwrap_or_else(|| env::consts::ARCH.to_string());
+                Self::from_linux_arch(&arch)
+            }
+            _ => Platform::Unsupported,
         }
     }
+
+    /// The Linux platform for a machine name from `uname -m`.
+    fn from_linux_arch(arch: &str) -> Self {
+        match arch {
+            "x86_64" | "amd64" => Platform::Linux64,
+            "x86" | "i386" | "i586" | "i686" => Platform::Linux32,
+            "aarch64" | "arm64" => Platform::LinuxArm64,
+            // Includes armv8l, a 32-bit userland on a 64-bit CPU, which can't run arm64 builds.
+            arch if arch.starts_with("arm") => Platform::LinuxArm32,
+            _ => Platform::Unsupported,
+        }
+    }
+
+    /// Whether engine builds for this platform can run on the machine we're running on.
+    pub fn runs_on_host(self) -> bool {
+        let host = Self::host();
+        self == host ||
+            (host == Platform::Linux64 && matches!(self, Platform::LinuxHeadless | Platform::LinuxServer))
+    }
+
+    pub fn is_windows(self) -> bool {
+        matches!(self, Platform::Windows32 | Platform::Windows64 | Platform::WindowsArm64)
+    }
+
+    /// Whether this is a Linux build with rendering, as opposed to the headless or server builds.
+    pub fn is_linux_desktop(self) -> bool {
+        matches!(self, Platform::Linux32 | Platform::Linux64 | Platform::LinuxArm32 | Platform::LinuxArm64)
+    }
+
+    /// The name used for this platform on the command line and in lockfiles, e.g. `linux-64`.
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The first Godot version with a build for this platform, as `(major, minor, patch)`.
+    pub fn first_version(self, mono: bool) -> Option<assets::Number> {
+        assets::first_version(self, mono)
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Platform::Windows32 => "Windows 32-bit",
+            Platform::Windows64 => "Windows 64-bit",
+            Platform::WindowsArm64 => "Windows ARM64",
+            Platform::MacOS => "macOS",
+            Platform::Linux32 => "Linux 32-bit",
+            Platform::Linux64 => "Linux 64-bit",
+            Platform::LinuxArm32 => "Linux ARM32",
+            Platform::LinuxArm64 => "Linux ARM64",
+            Platform::LinuxHeadless => "Linux headless",
+            Platform::LinuxServer => "Linux server",
+            Platform::Unsupported => "an unsupported platform",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_linux_arch() {
+        assert_eq!(Platform::from_linux_arch("x86_64"), Platform::Linux64);
+        assert_eq!(Platform::from_linux_arch("i686"), Platform::Linux32);
+        assert_eq!(Platform::from_linux_arch("aarch64"), Platform::LinuxArm64);
+        assert_eq!(Platform::from_linux_arch("armv8l"), Platform::LinuxArm32);
+        assert_eq!(Platform::from_linux_arch("armv7l"), Platform::LinuxArm32);
+        assert_eq!(Platform::from_linux_arch("riscv64"), Platform::Unsupported);
+    }
 }
@@ -0,0 +1,87 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+const BAR_WIDTH: usize = 30;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A single line progress bar for downloads, showing bytes downloaded, total size, speed, and ETA.
+/// Does nothing when stdout isn't a terminal, so logs and pipes don't fill up with redraws.
+pub struct ProgressBar {
+    total: Option<u64>,
+    current: u64,
+    /// Where the bar started, e.g. when resuming a download. Not counted towards speed.
+    start_position: u64,
+    start: Instant,
+    last_draw: Option<Instant>,
+    enabled: bool,
+}
+
+impl ProgressBar {
+    pub fn new(total: Option<u64>, position: u64) -> Self {
+        Self {
+            total,
+            current: position,
+            start_position: position,
+            start: Instant::now(),
+            last_draw: None,
+            enabled: io::stdout().is_terminal(),
+        }
+    }
+
+    pub fn inc(&mut self, bytes: u64) {
+        self.current += bytes;
+        let should_draw = self.last_draw
+            .map_or(true, |last_draw| last_draw.elapsed() >= REDRAW_INTERVAL);
+        if should_draw {
+            self.draw();
+        }
+    }
+
+    /// Draw the final state of the bar and move to the next line.
+    pub fn finish(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        self.draw();
+        println!();
+    }
+
+    fn draw(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let transferred = self.current - self.start_position;
+        let speed = if elapsed > 0.0 { transferred as f64 / elapsed } else { 0.0 };
+        let current = humansize::format_size(self.current, humansize::DECIMAL);
+        let speed_str = format!("{}/s", humansize::format_size(speed as u64, humansize::DECIMAL));
+
+        let line = match self.total {
+            Some(total) if total > 0 => {
+                let fraction = (self.current as f64 / total as f64).min(1.0);
+                let filled = (fraction * BAR_WIDTH as f64) as usize;
+                let bar = format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+                let total_str = humansize::format_size(total, humansize::DECIMAL);
+                let eta = if speed > 0.0 {
+                    format_eta((total.saturating_sub(self.current) as f64 / speed) as u64)
+                } else {
+                    "--:--".to_string()
+                };
+                format!("[{}] {} / {} {} ETA {}", bar, current, total_str, speed_str, eta)
+            }
+            _ => format!("{} {}", current, speed_str),
+        };
+
+        // Pad to clear leftovers from a longer previous line.
+        print!("\r{:<80}", line);
+        let _ = io::stdout().flush();
+    }
+}
+
+fn format_eta(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -0,0 +1,234 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    commands::{display_version, get_asset_names, installed_versions},
+    config::{self, ProjectFygConfig, PROJECT_FYG_CONFIGS},
+    dirs::FygDirs,
+    engine::EngineInstall,
+    lock::Lockfile,
+    output,
+    platform::Platform,
+    project_godot::{ProjectGodot, PROJECT_GODOT_NAME},
+    sources::{self, Source},
+    version::{GodotVersion, VersionReq},
+};
+
+/// A Godot project and the engine versions it can use.
+#[derive(Clone, Debug)]
+pub struct Project {
+    /// The fyg config the project was found from. `None` if its version came from project.godot.
+    pub config_path: Option<PathBuf>,
+    /// Directory holding project.godot, after applying the config's `root`.
+    pub godot_dir: PathBuf,
+    pub requirement: VersionReq,
+    /// Whether the project uses the Mono version of Godot with C# support.
+    pub mono: bool,
+    /// Whether the project's config asks for export templates.
+    pub export_templates: bool,
+    /// Where the requirement came from, for showing to the user.
+    pub requirement_source: String,
+    /// Where the project's lockfile goes, next to its fyg config. `None` without a fyg config.
+    pub lock_path: Option<PathBuf>,
+    /// The project's lockfile, if it's been locked.
+    pub lock: Option<Lockfile>,
+}
+
+impl Project {
+    /// Find the project containing `start_dir`. Looks for the nearest fyg config first, and then
+    /// for the nearest project.godot. Pass `mono` to use a Mono build even if the project doesn't
+    /// ask for one.
+    pub fn find(start_dir: &Path, mono: bool) -> Result<Project> {
+        match Self::discover(start_dir, mono)? {
+            Some(project) => Ok(project),
+            None => bail!(
+                "No config file ({}) or {} found in {} or its parents.",
+                PROJECT_FYG_CONFIGS.join(", "),
+                PROJECT_GODOT_NAME,
+                start_dir.display(),
+            ),
+        }
+    }
+
+    /// Like `find`, but returns `None` when `start_dir` isn't in a project.
+    pub fn discover(start_dir: &Path, mono: bool) -> Result<Option<Project>> {
+        let start_dir = fs::canonicalize(start_dir)
+            .with_context(|| format!("Could not find directory {}.", start_dir.display()))?;
+
+        if let Some(config_path) = ProjectFygConfig::find_nearest(&start_dir) {
+            let project_config = ProjectFygConfig::load_file(&config_path)?;
+            let config_dir = config_path.parent()
+                .unwrap_or(&start_dir);
+            let godot_dir = match &project_config.root {
+                Some(dir) if dir.is_relative() => config_dir.join(dir),
+                Some(dir) => dir.clone(),
+                None => config_dir.to_owned(),
+            };
+            let lock_path = Lockfile::path(&config_path);
+            let lock = Lockfile::load(&lock_path)?;
+            return Ok(Some(Project {
+                requirement_source: config_path.display().to_string(),
+                lock_path: Some(lock_path),
+                lock,
+                config_path: Some(config_path),
+                godot_dir,
+                requirement: project_config.version,
+                mono: mono || project_config.mono,
+                export_templates: project_config.export_templates,
+            }));
+        }
+
+        // Without a fyg config, fall back to the version project.godot was made with.
+        let find_project_godot = |dir: &Path| Some(dir.join(PROJECT_GODOT_NAME))
+            .filter(|path| path.is_file());
+        let Some(project_godot_path) = config::find_upwards(&start_dir, find_project_godot) else {
+            return Ok(None);
+        };
+        let project_godot = ProjectGodot::load(&project_godot_path)?;
+        let Some(requirement) = project_godot.requirement() else {
+            bail!(
+                "Could not find which Godot version {} was made with. Add a fyg.toml with the version to use.",
+                project_godot_path.display(),
+            );
+        };
+        Ok(Some(Project {
+            config_path: None,
+            godot_dir: project_godot_path.parent()
+                .unwrap_or(&start_dir)
+                .to_owned(),
+            requirement,
+            mono: mono || project_godot.uses_csharp,
+            export_templates: false,
+            requirement_source: format!("{} in {}", project_godot.version_key(), project_godot_path.display()),
+            lock_path: None,
+            lock: None,
+        }))
+    }
+
+    pub fn project_godot_path(&self) -> PathBuf {
+        self.godot_dir.join(PROJECT_GODOT_NAME)
+    }
+
+    /// The project's lockfile, after checking that it still matches the project's config.
+    pub fn checked_lock(&self) -> Result<Option<&Lockfile>> {
+        let (Some(lock), Some(lock_path)) = (&self.lock, &self.lock_path) else {
+            return Ok(None);
+        };
+        if !self.requirement.matches(&lock.version) {
+            bail!(
+                "{} pins version {}, which doesn't match {} from {}. Run `fyg lock` to update it.",
+                lock_path.display(),
+                lock.version,
+                self.requirement,
+                self.requirement_source,
+            );
+        }
+        if lock.mono != self.mono {
+            bail!(
+                "{} pins the {} build, but the {} build was asked for. Run `fyg lock` to update it.",
+                lock_path.display(),
+                if lock.mono { "Mono" } else { "standard" },
+                if self.mono { "Mono" } else { "standard" },
+            );
+        }
+        Ok(Some(lock))
+    }
+
+    /// The lockfile's checksum for this platform's zip, if the project is locked.
+    pub fn locked_sum(&self) -> Option<&str> {
+        self.lock.as_ref()?
+            .asset(Platform::get())?
+            .sha512
+            .as_deref()
+    }
+
+    /// Check that an installed engine was extracted from the zip the lockfile pins.
+    pub fn check_lock(&self, engine: &EngineInstall) -> Result<()> {
+        let (Some(locked_sum), Some(lock_path)) = (self.locked_sum(), &self.lock_path) else {
+            return Ok(());
+        };
+        match engine.read_package_sum()? {
+            Some(sum) if sum == locked_sum => Ok(()),
+            Some(_) => bail!(
+                "The installed engine at {} does not match {}. Reinstall it with `fyg install --force`.",
+                engine.dir.display(),
+                lock_path.display(),
+            ),
+            None => {
+                let warning = format!(
+                    "Warning: Can't check the engine at {} against {} since it was installed by an older fyg. Reinstall it with `fyg install --force` to check it.",
+                    engine.dir.display(),
+                    lock_path.display(),
+                );
+                output::warn(&warning);
+                Ok(())
+            }
+        }
+    }
+
+    /// The installed version to use. For locked projects and exact requirements, that's the
+    /// pinned version even if it isn't installed. For ranges it's the newest installed match.
+    pub fn installed_version(&self) -> Result<GodotVersion> {
+        if let Some(lock) = self.checked_lock()? {
+            return Ok(lock.version.clone());
+        }
+        if let Some(version) = self.requirement.exact() {
+            return Ok(version.clone());
+        }
+
+        let newest = installed_versions(FygDirs::get().engines_data(), self.mono)?
+            .into_iter()
+            .find(|version| self.requirement.matches(version));
+        match newest {
+            Some(version) => Ok(version),
+            None => bail!(
+                "No installed{} version matches {} from {}. Run `fyg install` to install the newest match.",
+                if self.mono { " Mono" } else { "" },
+                self.requirement,
+                self.requirement_source,
+            ),
+        }
+    }
+
+    /// The newest release matching the project's requirement with a build for this platform.
+    pub async fn newest_release(&self, sources: &[Source]) -> Result<GodotVersion> {
+        let releases = sources::list_releases(sources, false).await?;
+        let newest = releases.into_iter()
+            .filter(|release| self.requirement.matches(&release.version))
+            .filter(|release| get_asset_names(&release.version, self.mono)
+                .is_ok_and(|asset_names| release.has_asset(&asset_names.zip)))
+            .map(|release| release.version)
+            .max();
+        match newest {
+            Some(version) => Ok(version),
+            None => bail!(
+                "No release matching {} from {} has a build for {}.",
+                self.requirement,
+                self.requirement_source,
+                Platform::get(),
+            ),
+        }
+    }
+
+    /// Describe a version resolved for this project and where it came from, e.g.
+    /// "4.2.1, the newest installed match for ~4.2, from /path/to/fyg.toml".
+    pub fn describe_version(&self, version: &GodotVersion) -> String {
+        let display_version = display_version(version, self.mono);
+        if let (Some(_), Some(lock_path)) = (&self.lock, &self.lock_path) {
+            format!("{}, from {}", display_version, lock_path.display())
+        } else if self.requirement.exact().is_some() {
+            format!("{}, from {}", display_version, self.requirement_source)
+        } else {
+            format!(
+                "{}, the newest installed match for {}, from {}",
+                display_version,
+                self.requirement,
+                self.requirement_source,
+            )
+        }
+    }
+}
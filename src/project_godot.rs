@@ -0,0 +1,105 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::version::VersionReq;
+
+pub static PROJECT_GODOT_NAME: &str = "project.godot";
+
+/// Feature tag Godot adds to projects that use C#.
+const CSHARP_FEATURE: &str = "C#";
+
+/// What a project.godot file says about the engine the project was made with. Godot 4 lists its
+/// `major.minor` version in `config/features`, while Godot 3 only has `config_version`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectGodot {
+    /// Version of the project.godot format. 3 is Godot 3.0, 4 is Godot 3.1 and later 3.x, and
+    /// 5 is Godot 4.
+    pub config_version: Option<u32>,
+    /// The `major.minor` version from `config/features`.
+    pub feature_version: Option<(u32, u32)>,
+    /// Whether the project uses C#, and so needs a Mono build.
+    pub uses_csharp: bool,
+}
+
+impl ProjectGodot {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}.", path.display()))?;
+        Ok(Self::parse(&contents))
+    }
+
+    /// Parse the parts of a project.godot we care about. It's an INI-like format, where
+    /// `config_version` comes before any section and `config/features` is in `[application]`.
+    pub fn parse(contents: &str) -> Self {
+        let mut project = Self::default();
+        let mut section = "";
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = name;
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            match (section, key.trim()) {
+                ("", "config_version") => project.config_version = value.trim().parse().ok(),
+                ("application", "config/features") => {
+                    let features = parse_string_array(value);
+                    project.feature_version = features.iter()
+                        .find_map(|feature| parse_feature_version(feature));
+                    project.uses_csharp = features.iter()
+                        .any(|feature| feature == CSHARP_FEATURE);
+                }
+                _ => {}
+            }
+        }
+        project
+    }
+
+    /// The versions that can open this project without converting it, e.g. `4.2.x`. Returns
+    /// `None` if the project doesn't say which version it was made with.
+    pub fn requirement(&self) -> Option<VersionReq> {
+        let requirement = match (self.feature_version, self.config_version) {
+            (Some((major, minor)), _) => format!("{}.{}.x", major, minor),
+            (None, Some(3)) => "3.0.x".to_string(),
+            (None, Some(4)) => ">=3.1, <4.0".to_string(),
+            (None, Some(5)) => "4.x".to_string(),
+            _ => return None,
+        };
+        requirement.parse().ok()
+    }
+
+    /// Describe where the version came from in the file, e.g. `config/features`.
+    pub fn version_key(&self) -> &'static str {
+        if self.feature_version.is_some() {
+            "config/features"
+        } else {
+            "config_version"
+        }
+    }
+}
+
+/// Parse the strings out of a value like `PackedStringArray("4.2", "C#", "Forward Plus")`.
+fn parse_string_array(value: &str) -> Vec<String> {
+    let Some((_, rest)) = value.split_once('(') else {
+        return Vec::new();
+    };
+    let items = rest.rsplit_once(')')
+        .map_or(rest, |(items, _)| items);
+    items.split(',')
+        .map(|item| item.trim().trim_matches('"').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Parse a feature tag like "4.2" into its major and minor version.
+fn parse_feature_version(feature: &str) -> Option<(u32, u32)> {
+    let (major, minor) = feature.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
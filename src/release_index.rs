@@ -0,0 +1,131 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dirs::FygDirs,
+    sources::ReleaseInfo,
+};
+
+const RELEASE_INDEX_NAME: &str = "releases.json";
+
+/// How long a cached release list is used before it's fetched again, unless configured.
+pub const DEFAULT_TTL_HOURS: u32 = 24;
+
+/// How to use the cached release index for the rest of the run.
+#[derive(Clone, Copy, Debug)]
+pub struct IndexPolicy {
+    /// Never go online. Releases come from the cached index regardless of age, and engines from
+    /// the download cache.
+    pub offline: bool,
+    /// How long a cached release list stays fresh.
+    pub ttl: Duration,
+    /// Fetch release lists again even if they're fresh.
+    pub refresh: bool,
+}
+
+impl Default for IndexPolicy {
+    fn default() -> Self {
+        Self {
+            offline: false,
+            ttl: ttl_from_hours(DEFAULT_TTL_HOURS),
+            refresh: false,
+        }
+    }
+}
+
+static POLICY: OnceLock<IndexPolicy> = OnceLock::new();
+
+/// Set how the release index is used for the rest of the run. Must be called before any
+/// releases are looked up.
+pub fn init(policy: IndexPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+pub fn policy() -> IndexPolicy {
+    *POLICY.get_or_init(IndexPolicy::default)
+}
+
+pub fn is_offline() -> bool {
+    policy().offline
+}
+
+pub fn ttl_from_hours(hours: u32) -> Duration {
+    Duration::from_secs(u64::from(hours) * 60 * 60)
+}
+
+/// Every source's release list, cached in a single file.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ReleaseIndex {
+    /// Release lists keyed by the source they came from, as displayed.
+    sources: BTreeMap<String, IndexedReleases>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct IndexedReleases {
+    /// When the list was fetched, in seconds since the Unix epoch.
+    fetched_at: u64,
+    releases: Vec<ReleaseInfo>,
+}
+
+/// A source's cached release list.
+pub struct CachedReleases {
+    pub releases: Vec<ReleaseInfo>,
+    /// Whether the list is younger than the TTL.
+    pub is_fresh: bool,
+}
+
+pub fn path() -> PathBuf {
+    FygDirs::get().cache()
+        .join(RELEASE_INDEX_NAME)
+}
+
+/// Load the index, treating a missing or unreadable one as empty since it can be fetched again.
+fn load_index() -> ReleaseIndex {
+    fs::read_to_string(path())
+        .ok()
+        .and_then(|index_str| serde_json::from_str(&index_str).ok())
+        .unwrap_or_default()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs())
+}
+
+/// Load the cached release list for a source, if the index has one.
+pub fn load(source_key: &str) -> Option<CachedReleases> {
+    let indexed = load_index()
+        .sources
+        .remove(source_key)?;
+    let age = Duration::from_secs(now().saturating_sub(indexed.fetched_at));
+    Some(CachedReleases {
+        releases: indexed.releases,
+        is_fresh: age < policy().ttl,
+    })
+}
+
+/// Cache a source's release list, replacing any older one.
+pub fn save(source_key: &str, releases: &[ReleaseInfo]) -> Result<()> {
+    let mut index = load_index();
+    index.sources.insert(source_key.to_string(), IndexedReleases {
+        fetched_at: now(),
+        releases: releases.to_vec(),
+    });
+
+    let index_path = path();
+    fs::create_dir_all(FygDirs::get().cache())
+        .with_context(|| format!("Could not create {}.", FygDirs::get().cache().display()))?;
+    let index_str = serde_json::to_string(&index)
+        .context("Could not serialize the release index.")?;
+    fs::write(&index_path, index_str)
+        .with_context(|| format!("Could not write {}.", index_path.display()))
+}
@@ -0,0 +1,643 @@
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use octocrab::{models::repos::Release, Octocrab};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checksum,
+    commands::display_version,
+    output,
+    release_index,
+    version::GodotVersion,
+};
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+const GODOT_OWNER: &str = "godotengine";
+const GODOT_REPO: &str = "godot";
+/// Godot publishes dev snapshots, alphas, betas, and release candidates here rather than in its
+/// main repo.
+const GODOT_PRERELEASE_REPO: &str = "godot-builds";
+
+/// Where to find Godot releases. Sources are tried in the order they're configured, falling back
+/// to the next one when a source doesn't have a version or can't be reached.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Source {
+    /// GitHub's releases API, or a compatible API such as GitHub Enterprise.
+    Github {
+        #[serde(default = "default_github_api_url")]
+        api_url: String,
+        #[serde(default = "default_github_owner")]
+        owner: String,
+        #[serde(default = "default_github_repo")]
+        repo: String,
+        /// The repo pre-release versions are published in, under the same owner.
+        #[serde(default = "default_github_prerelease_repo")]
+        prerelease_repo: String,
+    },
+    /// An archive with the same layout as https://downloads.tuxfamily.org/godotengine/, where
+    /// files live under `<number>/[<stage>/][mono/]`, e.g. `4.3/beta2/mono/`.
+    Archive {
+        url: String,
+    },
+    /// A plain HTTP mirror, where files live under `<full version>/`, e.g. `4.2.1-stable/`.
+    Mirror {
+        url: String,
+    },
+}
+
+fn default_github_api_url() -> String {
+    GITHUB_API_URL.to_string()
+}
+
+fn default_github_owner() -> String {
+    GODOT_OWNER.to_string()
+}
+
+fn default_github_repo() -> String {
+    GODOT_REPO.to_string()
+}
+
+fn default_github_prerelease_repo() -> String {
+    GODOT_PRERELEASE_REPO.to_string()
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self::Github {
+            api_url: default_github_api_url(),
+            owner: default_github_owner(),
+            repo: default_github_repo(),
+            prerelease_repo: default_github_prerelease_repo(),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Github { api_url, owner, repo, .. } => write!(f, "{}", github_repo_name(api_url, owner, repo)),
+            Self::Archive { url } => write!(f, "archive {}", url),
+            Self::Mirror { url } => write!(f, "mirror {}", url),
+        }
+    }
+}
+
+/// A file published with a release.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AssetInfo {
+    pub name: String,
+    pub url: String,
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// A release and the files published with it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReleaseInfo {
+    pub version: GodotVersion,
+    /// When the release was published, in RFC 3339 format.
+    pub published_at: Option<String>,
+    pub assets: Vec<AssetInfo>,
+}
+
+impl ReleaseInfo {
+    fn from_github(release: &Release) -> Option<Self> {
+        let version = release.tag_name.parse().ok()?;
+        let assets = release.assets.iter()
+            .map(|asset| AssetInfo {
+                name: asset.name.clone(),
+                url: asset.browser_download_url.to_string(),
+                size: u64::try_from(asset.size).unwrap_or_default(),
+            })
+            .collect();
+        Some(Self {
+            version,
+            published_at: release.published_at.map(|date| date.to_rfc3339()),
+            assets,
+        })
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&AssetInfo> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    pub fn has_asset(&self, name: &str) -> bool {
+        self.asset(name).is_some()
+    }
+}
+
+/// A package to download and the checksum it should have, if the source publishes one.
+#[derive(Clone, Debug)]
+pub struct ResolvedPackage {
+    pub url: String,
+    /// Where the package was found, for showing to the user.
+    pub source: String,
+    pub expected_sum: Option<String>,
+}
+
+/// What a source knows about a version.
+enum Lookup {
+    Found(ResolvedPackage),
+    /// The source has the version, but not a package for this platform.
+    NoPackage,
+    NotFound,
+}
+
+impl Source {
+    /// Whether this is a GitHub source for an API other than github.com's, such as GitHub
+    /// Enterprise.
+    pub fn is_custom_github(&self) -> bool {
+        matches!(self, Self::Github { api_url, .. } if api_url != GITHUB_API_URL)
+    }
+
+    fn github_client(api_url: &str) -> Result<Arc<Octocrab>> {
+        let token = github_token();
+        if api_url == GITHUB_API_URL && token.is_none() {
+            return Ok(octocrab::instance());
+        }
+        let mut builder = Octocrab::builder()
+            .base_uri(api_url)?;
+        if let Some(token) = token {
+            builder = builder.personal_token(token.to_string());
+        }
+        let octocrab = builder.build()
+            .with_context(|| format!("Could not create a GitHub client for {}.", api_url))?;
+        Ok(Arc::new(octocrab))
+    }
+
+    /// Describe where this source looks for a version, e.g. the pre-release repo for betas.
+    fn describe_for(&self, version: &GodotVersion) -> String {
+        match self {
+            Self::Github { api_url, owner, prerelease_repo, .. } if !version.is_stable() => {
+                github_repo_name(api_url, owner, prerelease_repo)
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// Get a version's release from GitHub, or from the release index if it's been listed before.
+    /// Pre-release versions come from the pre-release repo. Returns `None` if there's no release
+    /// for it.
+    async fn github_release(&self, version: &GodotVersion) -> Result<Option<ReleaseInfo>> {
+        let Self::Github { api_url, owner, repo, prerelease_repo } = self else {
+            return Ok(None);
+        };
+        let repo = if version.is_stable() { repo } else { prerelease_repo };
+
+        // A release's assets don't change once published, so a stale index is fine here.
+        let indexed = release_index::load(&github_repo_name(api_url, owner, repo))
+            .and_then(|cached| cached.releases.into_iter().find(|release| release.version == *version));
+        if indexed.is_some() {
+            return Ok(indexed);
+        }
+        if release_index::is_offline() {
+            return Ok(None);
+        }
+
+        let octocrab = Self::github_client(api_url)?;
+        match octocrab.repos(owner, repo)
+            .releases()
+            .get_by_tag(&version.full_version())
+            .await
+        {
+            Ok(release) => Ok(ReleaseInfo::from_github(&release)),
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code == StatusCode::NOT_FOUND => Ok(None),
+            Err(e) => Err(github_error(&octocrab, e).await),
+        }
+    }
+
+    /// The directory URL holding the files for a version, for sources that are plain HTTP.
+    fn dir_url(&self, version: &GodotVersion, mono: bool) -> Option<String> {
+        match self {
+            Self::Github { .. } => None,
+            Self::Archive { url } => {
+                let mut dir_url = format!("{}/{}/", url.trim_end_matches('/'), version.number());
+                if !version.is_stable() {
+                    dir_url.push_str(&format!("{}/", version.stage));
+                }
+                if mono {
+                    dir_url.push_str("mono/");
+                }
+                Some(dir_url)
+            }
+            Self::Mirror { url } => Some(format!("{}/{}/", url.trim_end_matches('/'), version.full_version())),
+        }
+    }
+
+    async fn lookup(&self, client: &Client, version: &GodotVersion, mono: bool, file_name: &str) -> Result<Lookup> {
+        if let Self::Github { .. } = self {
+            let Some(release) = self.github_release(version).await? else {
+                return Ok(Lookup::NotFound);
+            };
+
+            let Some(url) = release.asset(file_name).map(|asset| asset.url.clone()) else {
+                return Ok(Lookup::NoPackage);
+            };
+            let expected_sum = match release.asset(checksum::SHA512_SUMS_NAME) {
+                Some(sums_asset) => fetch_sum(client, &sums_asset.url, file_name).await?,
+                None => None,
+            };
+            return Ok(Lookup::Found(ResolvedPackage { url, source: self.describe_for(version), expected_sum }));
+        }
+
+        let dir_url = self.dir_url(version, mono)
+            .ok_or_else(|| anyhow!("{} has no download directory.", self))?;
+        let url = format!("{}{}", dir_url, file_name);
+        let status = client.head(&url)
+            .send()
+            .await?
+            .status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(Lookup::NotFound);
+        }
+        if !status.is_success() {
+            bail!("{} returned {}.", url, status);
+        }
+
+        let sums_url = format!("{}{}", dir_url, checksum::SHA512_SUMS_NAME);
+        let expected_sum = fetch_sum(client, &sums_url, file_name).await?;
+        Ok(Lookup::Found(ResolvedPackage { url, source: self.describe_for(version), expected_sum }))
+    }
+
+    /// Find the URL of a version's SHA512-SUMS.txt. Returns `None` if the source doesn't have it.
+    async fn sums_url(&self, version: &GodotVersion, mono: bool) -> Result<Option<String>> {
+        if let Self::Github { .. } = self {
+            let release = self.github_release(version).await?;
+            return Ok(release.and_then(|release| release.asset(checksum::SHA512_SUMS_NAME)
+                .map(|asset| asset.url.clone())));
+        }
+
+        Ok(self.dir_url(version, mono)
+            .map(|dir_url| format!("{}{}", dir_url, checksum::SHA512_SUMS_NAME)))
+    }
+
+    /// List this source's stable releases, plus pre-releases if `prerelease` is set. Returns
+    /// `None` for sources that can't list releases.
+    async fn list_releases(&self, prerelease: bool) -> Result<Option<Vec<ReleaseInfo>>> {
+        let Self::Github { api_url, owner, repo, prerelease_repo } = self else {
+            return Ok(None);
+        };
+
+        let mut releases = list_github_releases(api_url, owner, repo).await?;
+        if !prerelease {
+            releases.retain(|release| release.version.is_stable());
+        } else if prerelease_repo != repo {
+            // The pre-release repo has the stable releases too, so only take what the main repo
+            // doesn't have.
+            let prereleases = list_github_releases(api_url, owner, prerelease_repo).await?;
+            releases.extend(prereleases.into_iter().filter(|release| !release.version.is_stable()));
+        }
+        Ok(Some(releases))
+    }
+}
+
+/// How a GitHub repo is shown, and the key its releases are cached under in the release index.
+fn github_repo_name(api_url: &str, owner: &str, repo: &str) -> String {
+    format!("GitHub {}/{} ({})", owner, repo, api_url)
+}
+
+/// List every release in a GitHub repo, using the release index while it's fresh.
+async fn list_github_releases(api_url: &str, owner: &str, repo: &str) -> Result<Vec<ReleaseInfo>> {
+    let policy = release_index::policy();
+    let source_key = github_repo_name(api_url, owner, repo);
+    match release_index::load(&source_key) {
+        Some(cached) if policy.offline || (cached.is_fresh && !policy.refresh) => {
+            return Ok(cached.releases);
+        }
+        None if policy.offline => bail!(
+            "No cached release list for {}/{}. List releases without --offline to cache it.",
+            owner,
+            repo,
+        ),
+        _ => {}
+    }
+
+    let octocrab = Source::github_client(api_url)?;
+    let first_page = octocrab.repos(owner, repo)
+        .releases()
+        .list()
+        .per_page(100)
+        .send()
+        .await;
+    let mut page = match first_page {
+        Ok(page) => page,
+        Err(e) => return Err(github_error(&octocrab, e).await),
+    };
+
+    let mut releases = Vec::new();
+    loop {
+        releases.extend(page.items.iter().filter_map(ReleaseInfo::from_github));
+
+        // Try to get the next page, if any.
+        page = match octocrab
+            .get_page::<Release>(&page.next)
+            .await
+        {
+            Ok(Some(next_page)) => next_page,
+            Ok(None) => break,
+            Err(e) => return Err(github_error(&octocrab, e).await),
+        }
+    }
+
+    release_index::save(&source_key, &releases)?;
+    Ok(releases)
+}
+
+static GITHUB_TOKEN: OnceLock<Option<String>> = OnceLock::new();
+
+/// Use a token for GitHub's API for the rest of the run. Must be called before any releases are
+/// looked up.
+pub fn set_github_token(token: Option<String>) {
+    let _ = GITHUB_TOKEN.set(token);
+}
+
+fn github_token() -> Option<&'static str> {
+    GITHUB_TOKEN.get()?
+        .as_deref()
+}
+
+/// Describe what went wrong with a request to GitHub's API. When the rate limit is exceeded, say
+/// when it resets and how to raise it.
+async fn github_error(octocrab: &Octocrab, error: octocrab::Error) -> anyhow::Error {
+    match error {
+        octocrab::Error::GitHub { source, .. } => {
+            let status = source.status_code;
+            let is_rate_limited = matches!(status, StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS) &&
+                source.message.to_ascii_lowercase().contains("rate limit");
+            if is_rate_limited {
+                // Checking the rate limit doesn't count against it.
+                let resets = match octocrab.ratelimit().get().await {
+                    Ok(rate_limit) => format!(" It resets {}.", describe_reset(rate_limit.resources.core.reset)),
+                    Err(_) => String::new(),
+                };
+                let hint = if github_token().is_some() {
+                    ""
+                } else {
+                    " Set GITHUB_TOKEN or FYG_GITHUB_TOKEN, or run `fyg config set github_token <token>`, to raise the limit."
+                };
+                return anyhow!("GitHub's API rate limit was exceeded.{}{}", resets, hint);
+            }
+            if status == StatusCode::UNAUTHORIZED && github_token().is_some() {
+                return anyhow!(
+                    "GitHub rejected the configured token: {}. Check GITHUB_TOKEN, FYG_GITHUB_TOKEN, and the github_token setting.",
+                    source.message,
+                );
+            }
+            anyhow!("GitHub returned {}: {}", status, source.message)
+        }
+        // Octocrab includes backtraces when displaying these, so only show what went wrong.
+        octocrab::Error::Service { source, .. } => anyhow!("Could not reach GitHub: {}", source),
+        octocrab::Error::Hyper { source, .. } => anyhow!("Could not reach GitHub: {}", source),
+        e => e.into(),
+    }
+}
+
+/// Describe when a rate limit resets, given as seconds since the Unix epoch, e.g.
+/// "in 12 minutes (at 14:05 UTC)".
+fn describe_reset(reset: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+    let minutes = reset.saturating_sub(now).div_ceil(60);
+    let seconds_today = reset % (24 * 60 * 60);
+    let at = format!("{:02}:{:02} UTC", seconds_today / (60 * 60), seconds_today / 60 % 60);
+    match minutes {
+        0 => format!("now (at {})", at),
+        1 => format!("in 1 minute (at {})", at),
+        _ => format!("in {} minutes (at {})", minutes, at),
+    }
+}
+
+/// Fetch a SHA512-SUMS.txt file. Returns `None` if there's no sums file, which is the case for
+/// older releases.
+async fn fetch_sums_file(client: &Client, sums_url: &str) -> Result<Option<String>> {
+    if release_index::is_offline() {
+        bail!("Can't download {} while offline.", sums_url);
+    }
+    let response = client.get(sums_url)
+        .send()
+        .await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let sums = response.error_for_status()?
+        .text()
+        .await?;
+    Ok(Some(sums))
+}
+
+/// Fetch a SHA512-SUMS.txt file and find the expected sum for `file_name`.
+async fn fetch_sum(client: &Client, sums_url: &str, file_name: &str) -> Result<Option<String>> {
+    let sums = fetch_sums_file(client, sums_url).await?;
+    Ok(sums.and_then(|sums| checksum::find_sum(&sums, file_name)))
+}
+
+/// Fetch a version's SHA512-SUMS.txt from the first source that has it. Returns `None` if no
+/// source publishes sums for the version.
+pub async fn fetch_sums(sources: &[Source], client: &Client, version: &GodotVersion, mono: bool) -> Result<Option<String>> {
+    let mut errors = Vec::new();
+    for source in sources {
+        let result = match source.sums_url(version, mono).await {
+            Ok(Some(sums_url)) => fetch_sums_file(client, &sums_url).await,
+            Ok(None) => Ok(None),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(Some(sums)) => return Ok(Some(sums)),
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {:#}", source, e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!(
+            "Could not get checksums for version {} from any source. Errors:\n  {}",
+            display_version(version, mono),
+            errors.join("\n  "),
+        );
+    }
+    Ok(None)
+}
+
+/// Find the package named `file_name` for a version, trying each source in order.
+pub async fn resolve_package(
+    sources: &[Source],
+    client: &Client,
+    version: &GodotVersion,
+    mono: bool,
+    file_name: &str,
+) -> Result<ResolvedPackage> {
+    let display_version = display_version(version, mono);
+    if release_index::is_offline() {
+        bail!("{} for version {} isn't in the cache, and fyg is offline.", file_name, display_version);
+    }
+    let mut found_version = false;
+    let mut errors = Vec::new();
+    for source in sources {
+        match source.lookup(client, version, mono, file_name).await {
+            Ok(Lookup::Found(package)) => return Ok(package),
+            Ok(Lookup::NoPackage) => found_version = true,
+            Ok(Lookup::NotFound) => {}
+            Err(e) => errors.push(format!("{}: {:#}", source, e)),
+        }
+    }
+
+    let sources_list = sources.iter()
+        .map(|source| format!("\n  {}", source))
+        .collect::<String>();
+    if !errors.is_empty() {
+        bail!(
+            "Could not find version {} in any source. Errors:\n  {}",
+            display_version,
+            errors.join("\n  "),
+        );
+    }
+    if found_version {
+        bail!("Version {} does not support your platform. Tried sources:{}", display_version, sources_list);
+    }
+    // TODO: Get list of releases and print available releases.
+    bail!("Version {} not found. Tried sources:{}", display_version, sources_list);
+}
+
+/// List releases from every source that can list them, merged by version. A version listed by
+/// more than one source keeps the first source's release. Sources that fail are skipped with a
+/// warning, as long as one of them succeeds.
+pub async fn list_releases(sources: &[Source], prerelease: bool) -> Result<Vec<ReleaseInfo>> {
+    let mut releases: Vec<ReleaseInfo> = Vec::new();
+    let mut listed = false;
+    let mut errors = Vec::new();
+    for source in sources {
+        match source.list_releases(prerelease).await {
+            Ok(Some(source_releases)) => {
+                listed = true;
+                for release in source_releases {
+                    if !releases.iter().any(|listed| listed.version == release.version) {
+                        releases.push(release);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {:#}", source, e)),
+        }
+    }
+
+    if listed {
+        for error in &errors {
+            let warning = format!("Warning: Could not list releases from {}", error);
+            output::warn(&warning);
+        }
+        return Ok(releases);
+    }
+    if errors.is_empty() {
+        bail!("None of the configured sources can list releases. Add a GitHub source to list them.");
+    }
+    bail!("Could not list releases from any source. Errors:\n  {}", errors.join("\n  "));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        download,
+        test_util::{self, Response},
+    };
+
+    const ZIP_NAME: &str = "Godot_v4.2.1-stable_linux.x86_64.zip";
+    const ZIP_SUM: &str = "0123abcd";
+
+    /// Serve `files` by path on a local port, like a plain HTTP mirror. Anything else is a 404.
+    async fn serve(files: &[(&str, &str)]) -> String {
+        let files = files.iter()
+            .map(|&(path, body)| (path.to_string(), body.to_string()))
+            .collect::<HashMap<_, _>>();
+        test_util::serve(move |request| match files.get(&request.path) {
+            Some(body) => Response::new("200 OK", body.as_str()),
+            None => Response::new("404 Not Found", ""),
+        }).await
+    }
+
+    fn sums() -> String {
+        format!("{}  {}\n", ZIP_SUM, ZIP_NAME)
+    }
+
+    async fn resolve(sources: &[Source], tag: &str, mono: bool, file_name: &str) -> Result<ResolvedPackage> {
+        let version = tag.parse().unwrap();
+        resolve_package(sources, &download::client().unwrap(), &version, mono, file_name).await
+    }
+
+    #[tokio::test]
+    async fn falls_back_after_not_found() {
+        let sums = sums();
+        let url = serve(&[
+            ("/archive/4.2.1/Godot_v4.2.1-stable_linux.x86_64.zip", "zip"),
+            ("/archive/4.2.1/SHA512-SUMS.txt", &sums),
+        ]).await;
+        let sources = [
+            Source::Mirror { url: format!("{}/mirror", url) },
+            Source::Archive { url: format!("{}/archive/", url) },
+        ];
+
+        let package = resolve(&sources, "4.2.1", false, ZIP_NAME).await.unwrap();
+        assert_eq!(package.url, format!("{}/archive/4.2.1/{}", url, ZIP_NAME));
+        assert_eq!(package.expected_sum.as_deref(), Some(ZIP_SUM));
+        assert_eq!(package.source, sources[1].to_string());
+    }
+
+    #[tokio::test]
+    async fn falls_back_after_unreachable_source() {
+        let sums = sums();
+        let url = serve(&[
+            ("/mirror/4.2.1-stable/Godot_v4.2.1-stable_linux.x86_64.zip", "zip"),
+            ("/mirror/4.2.1-stable/SHA512-SUMS.txt", &sums),
+        ]).await;
+        let sources = [
+            Source::Mirror { url: String::from("http://127.0.0.1:1") },
+            Source::Mirror { url: format!("{}/mirror", url) },
+        ];
+
+        let package = resolve(&sources, "4.2.1", false, ZIP_NAME).await.unwrap();
+        assert_eq!(package.url, format!("{}/mirror/4.2.1-stable/{}", url, ZIP_NAME));
+        assert_eq!(package.expected_sum.as_deref(), Some(ZIP_SUM));
+    }
+
+    #[tokio::test]
+    async fn finds_pre_release_mono_in_archive() {
+        let zip_name = "Godot_v4.3-beta2_mono_linux_x86_64.zip";
+        let url = serve(&[(&format!("/4.3/beta2/mono/{}", zip_name), "zip")]).await;
+        let sources = [Source::Archive { url: url.clone() }];
+
+        let package = resolve(&sources, "4.3-beta2", true, zip_name).await.unwrap();
+        assert_eq!(package.url, format!("{}/4.3/beta2/mono/{}", url, zip_name));
+        // The archive doesn't publish sums for this version.
+        assert_eq!(package.expected_sum, None);
+    }
+
+    #[tokio::test]
+    async fn reports_not_found_in_any_source() {
+        let url = serve(&[]).await;
+        let sources = [
+            Source::Mirror { url: format!("{}/mirror", url) },
+            Source::Archive { url: format!("{}/archive", url) },
+        ];
+
+        let error = resolve(&sources, "4.2.1", false, ZIP_NAME).await.unwrap_err();
+        assert!(error.to_string().starts_with("Version 4.2.1 not found. Tried sources:"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn reports_errors_when_every_source_fails() {
+        let sources = [Source::Mirror { url: String::from("http://127.0.0.1:1") }];
+
+        let error = resolve(&sources, "4.2.1", false, ZIP_NAME).await.unwrap_err();
+        assert!(error.to_string().starts_with("Could not find version 4.2.1 in any source. Errors:"), "{}", error);
+    }
+}
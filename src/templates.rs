@@ -0,0 +1,114 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use directories::BaseDirs;
+
+use crate::{
+    engine::EngineInstall,
+    version::GodotVersion,
+};
+
+/// Directory inside the export templates package holding the templates.
+const PACKAGE_DIR: &str = "templates";
+
+/// File Godot reads to check which version a templates dir is for.
+const VERSION_FILE: &str = "version.txt";
+
+/// Export templates for an engine version, unpacked where that version's editor looks for them:
+/// https://docs.godotengine.org/en/stable/tutorials/export/exporting_projects.html#export-templates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportTemplates {
+    pub dir: PathBuf,
+}
+
+impl ExportTemplates {
+    /// Resolve where the editor of an installed engine looks for its export templates. Editors in
+    /// Self-Contained Mode look in their `editor_data` dir, and other editors in Godot's data dir
+    /// in the user's home directory.
+    pub fn new(engine: &EngineInstall, version: &GodotVersion, mono: bool) -> Result<Self> {
+        let data_dir = if engine.is_self_contained() {
+            engine.editor_data_dir()
+        } else {
+            let Some(base_dirs) = BaseDirs::new() else {
+                bail!("Could not find Godot's data directory.");
+            };
+            let godot_dir_name = if cfg!(target_os = "linux") { "godot" } else { "Godot" };
+            base_dirs.data_dir()
+                .join(godot_dir_name)
+        };
+
+        // Godot 4 renamed the dir so it isn't confused with script templates.
+        let templates_dir_name = if version.major >= 4 { "export_templates" } else { "templates" };
+        Ok(Self {
+            dir: data_dir.join(templates_dir_name)
+                .join(dir_name(version, mono)),
+        })
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.dir.join(VERSION_FILE).is_file()
+    }
+
+    /// Unpack an export templates package, replacing any templates already there.
+    pub fn extract(&self, tpz_path: &Path) -> Result<()> {
+        let Some(parent_dir) = self.dir.parent() else {
+            bail!("Could not extract {} to {}.", tpz_path.display(), self.dir.display());
+        };
+        fs::create_dir_all(parent_dir)
+            .with_context(|| format!("Could not create {}.", parent_dir.display()))?;
+
+        // The package holds a single `templates` dir, so unpack it alongside and rename it.
+        let mut unpack_dir_name = self.dir.file_name()
+            .unwrap_or_default()
+            .to_owned();
+        unpack_dir_name.push(".fyg-unpack");
+        let unpack_dir = parent_dir.join(unpack_dir_name);
+        if unpack_dir.exists() {
+            fs::remove_dir_all(&unpack_dir)
+                .with_context(|| format!("Could not remove {}.", unpack_dir.display()))?;
+        }
+
+        let tpz_file = fs::File::open(tpz_path)
+            .with_context(|| format!("Could not open {}.", tpz_path.display()))?;
+        let mut archive = zip::ZipArchive::new(tpz_file)
+            .with_context(|| format!("Could not read {} as a zip.", tpz_path.display()))?;
+        archive.extract(&unpack_dir)
+            .with_context(|| format!("Could not extract {}.", tpz_path.display()))?;
+
+        let unpacked_templates_dir = unpack_dir.join(PACKAGE_DIR);
+        if !unpacked_templates_dir.join(VERSION_FILE).is_file() {
+            let _ = fs::remove_dir_all(&unpack_dir);
+            bail!("Extracted {}, but it did not hold any export templates.", tpz_path.display());
+        }
+
+        self.remove()?;
+        fs::rename(&unpacked_templates_dir, &self.dir)
+            .with_context(|| format!("Could not move export templates to {}.", self.dir.display()))?;
+        fs::remove_dir_all(&unpack_dir)
+            .with_context(|| format!("Could not remove {}.", unpack_dir.display()))?;
+
+        Ok(())
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        if self.dir.is_dir() {
+            fs::remove_dir_all(&self.dir)
+                .with_context(|| format!("Could not remove {}.", self.dir.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of the dir Godot looks for a version's templates in, matching the `version.txt` inside,
+/// e.g. `4.2.1.stable` or `4.3.beta2.mono`.
+fn dir_name(version: &GodotVersion, mono: bool) -> String {
+    let dir_name = format!("{}.{}", version.number(), version.stage);
+    if mono {
+        format!("{}.mono", dir_name)
+    } else {
+        dir_name
+    }
+}
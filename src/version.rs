@@ -1,8 +1,485 @@
-pub fn get_full_version(version: &str) -> String {
-    // TODO: Use a more thorough heuristic.
-    if version.contains('-') {
-        version.to_string()
-    } else {
-        format!("{}-stable", version)
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// How far along a Godot release is. Ordered from least to most stable, so that e.g.
+/// `4.3-beta2 < 4.3-rc1 < 4.3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReleaseStage {
+    Dev(u32),
+    Alpha(u32),
+    Beta(u32),
+    Rc(u32),
+    Stable,
+}
+
+impl ReleaseStage {
+    fn parse(s: &str) -> Option<Self> {
+        if s == "stable" {
+            return Some(Self::Stable);
+        }
+
+        let (name, number) = s.split_at(s.find(|c: char| c.is_ascii_digit())?);
+        let number = number.parse().ok()?;
+        match name {
+            "dev" => Some(Self::Dev(number)),
+            "alpha" => Some(Self::Alpha(number)),
+            "beta" => Some(Self::Beta(number)),
+            "rc" => Some(Self::Rc(number)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReleaseStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dev(n) => write!(f, "dev{}", n),
+            Self::Alpha(n) => write!(f, "alpha{}", n),
+            Self::Beta(n) => write!(f, "beta{}", n),
+            Self::Rc(n) => write!(f, "rc{}", n),
+            Self::Stable => write!(f, "stable"),
+        }
+    }
+}
+
+/// A Godot engine version, like `4.2.1` or `4.3-beta2`.
+///
+/// Godot leaves off a zero patch number in its release tags (`4.2-stable`, not `4.2.0-stable`),
+/// so `4.2` and `4.2.0` parse to the same version. One release, `2.0.4.1`, has a fourth number.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct GodotVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    /// The fourth number of a hotfix release like `2.0.4.1`. Almost always 0.
+    pub hotfix: u32,
+    pub stage: ReleaseStage,
+}
+
+impl GodotVersion {
+    /// The full version as used in Godot's release tags and file names, e.g. `4.2.1-stable`.
+    pub fn full_version(&self) -> String {
+        format!("{}-{}", self.number(), self.stage)
+    }
+
+    /// Just the numeric part of the version, e.g. `4.2.1` or `4.3`.
+    pub fn number(&self) -> String {
+        if self.hotfix != 0 {
+            format!("{}.{}.{}.{}", self.major, self.minor, self.patch, self.hotfix)
+        } else if self.patch == 0 {
+            format!("{}.{}", self.major, self.minor)
+        } else {
+            format!("{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+
+    /// The version's numbers without its release stage, for comparing.
+    fn numbers(&self) -> (u32, u32, u32, u32) {
+        (self.major, self.minor, self.patch, self.hotfix)
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.stage == ReleaseStage::Stable
+    }
+}
+
+impl FromStr for GodotVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!(
+            "Invalid Godot version \"{}\". Expected a version like \"4.2\", \"4.2.1\", or \"4.3-beta2\".",
+            s,
+        );
+
+        let trimmed = s.trim();
+        let trimmed = trimmed.strip_prefix(['v', 'V'])
+            .unwrap_or(trimmed);
+        let (number, stage) = match trimmed.split_once('-') {
+            Some((number, stage)) => (number, ReleaseStage::parse(stage).ok_or_else(invalid)?),
+            None => (trimmed, ReleaseStage::Stable),
+        };
+
+        let mut parts = number.split('.')
+            .map(|part| part.parse::<u32>());
+        let (Some(Ok(major)), Some(Ok(minor))) = (parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let mut next_number = || match parts.next() {
+            Some(Ok(number)) => Ok(number),
+            Some(Err(_)) => Err(invalid()),
+            None => Ok(0),
+        };
+        let patch = next_number()?;
+        let hotfix = next_number()?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            hotfix,
+            stage,
+        })
+    }
+}
+
+impl TryFrom<String> for GodotVersion {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl Serialize for GodotVersion {
+    /// Serialize the full version, so the release stage is always explicit.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.full_version())
+    }
+}
+
+impl fmt::Display for GodotVersion {
+    /// Display stable versions without a suffix, since that's how users usually refer to them.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_stable() {
+            write!(f, "{}", self.number())
+        } else {
+            write!(f, "{}-{}", self.number(), self.stage)
+        }
+    }
+}
+
+/// The start of a version number, like `4`, `4.2`, or `4.2.1`, that matches every version
+/// beginning with it. `4.2` matches `4.2`, `4.2.1`, and `4.2-beta1`, but not `4.20`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionPrefix {
+    numbers: Vec<u32>,
+}
+
+impl VersionPrefix {
+    pub fn matches(&self, version: &GodotVersion) -> bool {
+        self.numbers.iter()
+            .zip([version.major, version.minor, version.patch])
+            .all(|(&prefix, number)| prefix == number)
+    }
+}
+
+impl FromStr for VersionPrefix {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let numbers = s.trim()
+            .split('.')
+            .map(|number| number.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()
+            .filter(|numbers| numbers.len() <= 3)
+            .ok_or_else(|| anyhow!(
+                "Invalid version filter \"{}\". Expected a version like \"4\", \"4.2\", or \"4.2.1\".",
+                s,
+            ))?;
+        Ok(Self { numbers })
+    }
+}
+
+/// How a comparator in a `VersionReq` compares versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    version: GodotVersion,
+}
+
+impl Comparator {
+    fn matches(&self, version: &GodotVersion) -> bool {
+        match self.op {
+            Op::Exact => version == &self.version,
+            Op::Greater => version > &self.version,
+            Op::GreaterEq => version >= &self.version,
+            // `<4.4` means before 4.4's development, so it excludes 4.4's pre-releases too.
+            Op::Less if self.version.is_stable() => version.numbers() < self.version.numbers(),
+            Op::Less => version < &self.version,
+            Op::LessEq => version <= &self.version,
+        }
+    }
+}
+
+/// Which versions a project can use. Either an exact version like `4.2.1`, or a range like
+/// `~4.2`, `4.2.x`, or `>=4.2, <4.3` that any matching patch release satisfies.
+///
+/// Ranges only match stable releases, unless one of their bounds is a pre-release. A stable upper
+/// bound like `<4.4` excludes that version's pre-releases as well.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct VersionReq {
+    comparators: Vec<Comparator>,
+    text: String,
+}
+
+impl VersionReq {
+    /// The version this requires, if it only allows one.
+    pub fn exact(&self) -> Option<&GodotVersion> {
+        match self.comparators.as_slice() {
+            [Comparator { op: Op::Exact, version }] => Some(version),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, version: &GodotVersion) -> bool {
+        let allows_prerelease = self.comparators.iter()
+            .any(|comparator| !comparator.version.is_stable());
+        (version.is_stable() || allows_prerelease || self.exact().is_some()) &&
+            self.comparators.iter().all(|comparator| comparator.matches(version))
+    }
+}
+
+/// Parse one part of a requirement, like `>=4.2`, `~4.2`, or `4.2.x`, into its comparators.
+fn parse_comparators(part: &str) -> Option<Vec<Comparator>> {
+    let stable = |major, minor, patch| GodotVersion { major, minor, patch, hotfix: 0, stage: ReleaseStage::Stable };
+    let comparator = |op, version| Comparator { op, version };
+
+    // Wildcards match any minor or patch release, e.g. `4.x` or `4.2.x`.
+    let wildcard = part.strip_suffix(".x")
+        .or_else(|| part.strip_suffix(".*"));
+    if let Some(number) = wildcard {
+        let numbers = number.split('.')
+            .map(|n| n.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let (lower, upper) = match *numbers.as_slice() {
+            [major] => (stable(major, 0, 0), stable(major + 1, 0, 0)),
+            [major, minor] => (stable(major, minor, 0), stable(major, minor + 1, 0)),
+            _ => return None,
+        };
+        return Some(vec![comparator(Op::GreaterEq, lower), comparator(Op::Less, upper)]);
+    }
+
+    let (prefix, rest) = ["~", "^", ">=", "<=", ">", "<", "="].iter()
+        .find_map(|&prefix| part.strip_prefix(prefix).map(|rest| (prefix, rest.trim())))
+        .unwrap_or(("", part));
+
+    // A bare major version is allowed in ranges, e.g. `~4` or `<5`.
+    let version = match rest.parse::<u32>() {
+        Ok(major) if !matches!(prefix, "" | "=") => stable(major, 0, 0),
+        _ => rest.parse::<GodotVersion>().ok()?,
+    };
+    let comparators = match prefix {
+        // `~4.2` allows patch releases, and `~4` allows minor releases.
+        "~" => {
+            let upper = if rest.contains('.') {
+                stable(version.major, version.minor + 1, 0)
+            } else {
+                stable(version.major + 1, 0, 0)
+            };
+            vec![comparator(Op::GreaterEq, version), comparator(Op::Less, upper)]
+        }
+        // `^4.2` allows minor and patch releases.
+        "^" => {
+            let upper = stable(version.major + 1, 0, 0);
+            vec![comparator(Op::GreaterEq, version), comparator(Op::Less, upper)]
+        }
+        ">=" => vec![comparator(Op::GreaterEq, version)],
+        "<=" => vec![comparator(Op::LessEq, version)],
+        ">" => vec![comparator(Op::Greater, version)],
+        "<" => vec![comparator(Op::Less, version)],
+        _ => vec![comparator(Op::Exact, version)],
+    };
+    Some(comparators)
+}
+
+impl FromStr for VersionReq {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!(
+            "Invalid version requirement \"{}\". Expected a version like \"4.2.1\", or a range like \"~4.2\", \"4.2.x\", or \">=4.2, <4.3\".",
+            s,
+        );
+
+        let mut comparators = Vec::new();
+        for part in s.split(',') {
+            let part = part.trim();
+            comparators.extend(parse_comparators(part).ok_or_else(invalid)?);
+        }
+
+        Ok(Self {
+            comparators,
+            text: s.trim().to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for VersionReq {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.exact() {
+            Some(version) => write!(f, "{}", version),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> GodotVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn leaves_off_zero_patch() {
+        assert_eq!(version("4.2"), version("4.2.0"));
+        assert_eq!(version("4.2"), version("4.2-stable"));
+        assert_eq!(version("4.2.0").full_version(), "4.2-stable");
+        assert_eq!(version("4.2.0").to_string(), "4.2");
+    }
+
+    #[test]
+    fn parses_release_tags() {
+        let parsed = version("v4.2.1-stable");
+        assert_eq!(parsed, GodotVersion { major: 4, minor: 2, patch: 1, hotfix: 0, stage: ReleaseStage::Stable });
+        assert_eq!(parsed.full_version(), "4.2.1-stable");
+        assert_eq!(parsed.to_string(), "4.2.1");
+        assert_eq!(version(" V4.2.1 "), parsed);
+    }
+
+    #[test]
+    fn parses_pre_releases() {
+        let beta = version("4.3-beta2");
+        assert_eq!(beta.stage, ReleaseStage::Beta(2));
+        assert!(!beta.is_stable());
+        assert_eq!(beta.full_version(), "4.3-beta2");
+        assert_eq!(beta.to_string(), "4.3-beta2");
+
+        assert_eq!(version("4.3-rc1").stage, ReleaseStage::Rc(1));
+        assert_eq!(version("4.4-dev3").stage, ReleaseStage::Dev(3));
+        assert_eq!(version("4.0-alpha17").stage, ReleaseStage::Alpha(17));
+    }
+
+    #[test]
+    fn parses_hotfix_releases() {
+        let hotfix = version("2.0.4.1-stable");
+        assert_eq!((hotfix.major, hotfix.minor, hotfix.patch, hotfix.hotfix), (2, 0, 4, 1));
+        assert_eq!(hotfix.full_version(), "2.0.4.1-stable");
+        assert!(hotfix > version("2.0.4"));
+        assert!(hotfix < version("2.0.5"));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        for invalid in ["", "4", "four", "4.x", "4.2.", "4..2", "4.2.1.0.1", "4.2-beta", "4.2-gamma1", "4.2-stable1", "-4.2", "4.2.1-"] {
+            assert!(invalid.parse::<GodotVersion>().is_err(), "{:?} should be invalid", invalid);
+        }
+    }
+
+    #[test]
+    fn orders_by_number_then_stage() {
+        let ordered = ["4.3-dev1", "4.3-dev2", "4.3-alpha1", "4.3-beta1", "4.3-beta2", "4.3-rc1", "4.3", "4.3.1-rc1", "4.3.1", "4.10"];
+        for pair in ordered.windows(2) {
+            assert!(version(pair[0]) < version(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert!(ReleaseStage::Dev(9) < ReleaseStage::Alpha(1));
+        assert!(ReleaseStage::Rc(9) < ReleaseStage::Stable);
+    }
+
+    fn req(s: &str) -> VersionReq {
+        s.parse().unwrap()
+    }
+
+    /// Assert which of `versions` a requirement matches.
+    fn assert_matches(requirement: &str, matching: &[&str], not_matching: &[&str]) {
+        let requirement = req(requirement);
+        for matching in matching {
+            assert!(requirement.matches(&version(matching)), "{} should match {}", requirement, matching);
+        }
+        for not_matching in not_matching {
+            assert!(!requirement.matches(&version(not_matching)), "{} should not match {}", requirement, not_matching);
+        }
+    }
+
+    #[test]
+    fn tilde_allows_patches_or_minors() {
+        assert_matches("~4.2", &["4.2", "4.2.1", "4.2.9"], &["4.1.3", "4.3", "4.3-beta1", "5.0"]);
+        assert_matches("~4.2.1", &["4.2.1", "4.2.2"], &["4.2", "4.3"]);
+        assert_matches("~4", &["4.0", "4.2.1", "4.9"], &["3.6", "5.0"]);
+    }
+
+    #[test]
+    fn caret_allows_minors() {
+        assert_matches("^4.2", &["4.2", "4.2.1", "4.3", "4.9.1"], &["4.1.3", "5.0", "4.4-beta1"]);
+    }
+
+    #[test]
+    fn wildcards_allow_minors_or_patches() {
+        assert_matches("4.2.x", &["4.2", "4.2.1"], &["4.1.3", "4.3"]);
+        assert_matches("4.2.*", &["4.2", "4.2.1"], &["4.3"]);
+        assert_matches("4.x", &["4.0", "4.3"], &["3.6", "5.0"]);
+        assert!("4.2.1.x".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn joins_bounds_with_commas() {
+        assert_matches(">=4.2, <4.3", &["4.2", "4.2.2"], &["4.1.3", "4.3"]);
+        assert_matches(">4.2,<=4.3", &["4.2.1", "4.3"], &["4.2", "4.3.1"]);
+    }
+
+    #[test]
+    fn allows_bare_major_bounds() {
+        assert_matches("<5", &["4.3", "3.6"], &["5.0"]);
+        assert_matches(">=4, <5", &["4.0", "4.9"], &["3.6", "5.0"]);
+        assert_matches("~4", &["4.1"], &["5.0"]);
+        // A bare number on its own isn't a range.
+        assert!("4".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn excludes_pre_releases_unless_a_bound_is_one() {
+        assert_matches("~4.2", &["4.2.1"], &["4.3-beta1", "4.2.2-rc1"]);
+        assert_matches(">=4.3-beta1, <4.4", &["4.3-beta1", "4.3-beta2", "4.3-rc1", "4.3", "4.3.1"], &["4.3-alpha1", "4.4-dev1", "4.4"]);
+    }
+
+    #[test]
+    fn exact_versions_match_only_themselves() {
+        let exact = req("4.3-beta2");
+        assert_eq!(exact.exact(), Some(&version("4.3-beta2")));
+        assert_matches("4.3-beta2", &["4.3-beta2"], &["4.3-beta1", "4.3"]);
+        assert_matches("=4.2.1", &["4.2.1"], &["4.2.2"]);
+        assert_eq!(req("~4.2").exact(), None);
+    }
+
+    #[test]
+    fn displays_requirements() {
+        assert_eq!(req("4.2.0").to_string(), "4.2");
+        assert_eq!(req(" >=4.2, <4.3 ").to_string(), ">=4.2, <4.3");
+    }
+
+    #[test]
+    fn rejects_invalid_requirements() {
+        for invalid in ["", "~", ">=", "~four", ">=4.2,", "4.2 || 4.3"] {
+            assert!(invalid.parse::<VersionReq>().is_err(), "{:?} should be invalid", invalid);
+        }
     }
 }